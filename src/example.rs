@@ -1,26 +1,26 @@
-use crate::model::constraint::{ConstraintSpecifier, Property};
+use crate::model::constraint::{Comparison, ConstraintSpecifier, Operation, Property, Strictness};
 use crate::model::region::{LineSpecifier, RegionSpecifier};
 use crate::model::SudokuSpecifier;
 use glam::IVec2;
 use std::path::PathBuf;
 
-fn sudoku_constraints() -> Vec<ConstraintSpecifier> {
+fn sudoku_constraints(origin: IVec2) -> Vec<ConstraintSpecifier> {
     let mut constraints = Vec::new();
     for i in 0..9 {
         constraints.push(ConstraintSpecifier::Unique {
             region: RegionSpecifier::Line {
-                points: vec![IVec2::new(0, i), IVec2::new(8, i)],
+                points: vec![origin + IVec2::new(0, i), origin + IVec2::new(8, i)],
             },
         });
         constraints.push(ConstraintSpecifier::Unique {
             region: RegionSpecifier::Line {
-                points: vec![IVec2::new(i, 0), IVec2::new(i, 8)],
+                points: vec![origin + IVec2::new(i, 0), origin + IVec2::new(i, 8)],
             },
         });
-        let box_start = IVec2::new(i % 3 * 3, i / 3 * 3);
+        let box_start = origin + IVec2::new(i % 3 * 3, i / 3 * 3);
         constraints.push(ConstraintSpecifier::Unique {
             region: RegionSpecifier::Box {
-                start: box_start.clone(),
+                start: box_start,
                 end: box_start + IVec2::new(2, 2),
             },
         });
@@ -28,6 +28,139 @@ fn sudoku_constraints() -> Vec<ConstraintSpecifier> {
     constraints
 }
 
+fn latin_square_constraints(size: i32) -> Vec<ConstraintSpecifier> {
+    let mut constraints = Vec::new();
+    for i in 0..size {
+        constraints.push(ConstraintSpecifier::Unique {
+            region: RegionSpecifier::Line {
+                points: vec![IVec2::new(0, i), IVec2::new(size - 1, i)],
+            },
+        });
+        constraints.push(ConstraintSpecifier::Unique {
+            region: RegionSpecifier::Line {
+                points: vec![IVec2::new(i, 0), IVec2::new(i, size - 1)],
+            },
+        });
+    }
+    constraints
+}
+
+pub fn latin_square(size: i32) -> SudokuSpecifier {
+    SudokuSpecifier {
+        size: IVec2::new(size, size),
+        numbers: vec![IVec2::new(1, size)],
+        constraints: latin_square_constraints(size),
+        domains: Vec::new(),
+        inactive: Vec::new(),
+    }
+}
+
+/// Makes every row, column and main diagonal of the 3x3 square at `corner` share a sum. Giving
+/// the sum up front, such as 15 for the digits 1 to 9, propagates far better than only requiring
+/// the lines to match the first row.
+pub fn magic_square_constraints(corner: IVec2, sum: Option<i32>) -> Vec<ConstraintSpecifier> {
+    let mut lines = Vec::new();
+    for i in 0..3 {
+        lines.push(
+            (0..3)
+                .map(|j| corner + IVec2::new(j, i))
+                .collect::<Vec<_>>(),
+        );
+        lines.push((0..3).map(|j| corner + IVec2::new(i, j)).collect());
+    }
+    lines.push((0..3).map(|i| corner + IVec2::new(i, i)).collect());
+    lines.push((0..3).map(|i| corner + IVec2::new(2 - i, i)).collect());
+    if let Some(sum) = sum {
+        return lines
+            .into_iter()
+            .map(|line| ConstraintSpecifier::Linear {
+                terms: line.iter().map(|cell| (*cell, 1)).collect(),
+                comparison: Comparison::Equal,
+                constant: sum,
+            })
+            .collect();
+    }
+    let first = lines[0].clone();
+    lines
+        .into_iter()
+        .skip(1)
+        .map(|line| {
+            let terms = line.iter().map(|cell| (*cell, 1));
+            let first_terms = first.iter().map(|cell| (*cell, -1));
+            ConstraintSpecifier::Linear {
+                terms: terms.chain(first_terms).collect(),
+                comparison: Comparison::Equal,
+                constant: 0,
+            }
+        })
+        .collect()
+}
+
+/// Overlapping 9x9 sudokus with their top left corners at `origins`. Cells outside every grid
+/// are inactive.
+pub fn multi_grid(origins: &[IVec2]) -> SudokuSpecifier {
+    let size = origins.iter().fold(IVec2::ZERO, |size, origin| {
+        size.max(*origin + IVec2::splat(9))
+    });
+    let in_component = |cell: IVec2| {
+        origins.iter().any(|origin| {
+            let offset = cell - *origin;
+            offset.cmpge(IVec2::ZERO).all() && offset.cmplt(IVec2::splat(9)).all()
+        })
+    };
+    let inactive = (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| IVec2::new(x, y)))
+        .filter(|cell| !in_component(*cell))
+        .collect::<Vec<_>>();
+    SudokuSpecifier {
+        size,
+        numbers: vec![IVec2::new(1, 9)],
+        constraints: origins
+            .iter()
+            .flat_map(|origin| sudoku_constraints(*origin))
+            .collect(),
+        domains: Vec::new(),
+        inactive: vec![RegionSpecifier::Many { cells: inactive }],
+    }
+}
+
+/// Four grids around a fifth, sharing a box at each of its corners.
+pub fn samurai() -> SudokuSpecifier {
+    multi_grid(&[
+        IVec2::new(0, 0),
+        IVec2::new(12, 0),
+        IVec2::new(6, 6),
+        IVec2::new(0, 12),
+        IVec2::new(12, 12),
+    ])
+}
+
+/// Two grids sharing a corner box.
+pub fn twodoku() -> SudokuSpecifier {
+    multi_grid(&[IVec2::new(0, 0), IVec2::new(6, 6)])
+}
+
+/// Four grids in a 12x12 square, each overlapping the others.
+pub fn butterfly() -> SudokuSpecifier {
+    multi_grid(&[
+        IVec2::new(0, 0),
+        IVec2::new(3, 0),
+        IVec2::new(0, 3),
+        IVec2::new(3, 3),
+    ])
+}
+
+/// A centre grid with four grids offset by one box in each direction.
+pub fn flower() -> SudokuSpecifier {
+    multi_grid(&[
+        IVec2::new(3, 3),
+        IVec2::new(0, 3),
+        IVec2::new(6, 3),
+        IVec2::new(3, 0),
+        IVec2::new(3, 6),
+    ])
+}
+
 fn given_constraints(constraints: &mut Vec<ConstraintSpecifier>, given: &Vec<(i32, Vec<IVec2>)>) {
     for (num, positions) in given {
         constraints.push(ConstraintSpecifier::Property {
@@ -40,7 +173,7 @@ fn given_constraints(constraints: &mut Vec<ConstraintSpecifier>, given: &Vec<(i3
 }
 
 pub fn test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
-    let mut constraints = sudoku_constraints();
+    let mut constraints = sudoku_constraints(IVec2::ZERO);
     given_constraints(
         &mut constraints,
         &vec![
@@ -79,36 +212,44 @@ pub fn test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
         line: LineSpecifier {
             points: vec![IVec2::new(0, 0), IVec2::new(0, 5)],
         },
+        strictness: Strictness::Strict,
     });
     constraints.push(ConstraintSpecifier::Thermometer {
         line: LineSpecifier {
             points: vec![IVec2::new(8, 0), IVec2::new(5, 0)],
         },
+        strictness: Strictness::Strict,
     });
     constraints.push(ConstraintSpecifier::Thermometer {
         line: LineSpecifier {
             points: vec![IVec2::new(4, 3), IVec2::new(2, 3)],
         },
+        strictness: Strictness::Strict,
     });
     constraints.push(ConstraintSpecifier::Thermometer {
         line: LineSpecifier {
             points: vec![IVec2::new(6, 5), IVec2::new(4, 5)],
         },
+        strictness: Strictness::Strict,
     });
     constraints.push(ConstraintSpecifier::Thermometer {
         line: LineSpecifier {
             points: vec![IVec2::new(8, 6), IVec2::new(8, 2)],
         },
+        strictness: Strictness::Strict,
     });
     constraints.push(ConstraintSpecifier::Thermometer {
         line: LineSpecifier {
             points: vec![IVec2::new(1, 8), IVec2::new(3, 8)],
         },
+        strictness: Strictness::Strict,
     });
     let specifier = SudokuSpecifier {
         size: IVec2::new(9, 9),
         numbers: vec![IVec2::new(1, 9)],
         constraints,
+        domains: Vec::new(),
+        inactive: Vec::new(),
     };
     if let Some(path) = path {
         specifier.to_file(path, true).unwrap();
@@ -117,7 +258,7 @@ pub fn test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
 }
 
 pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
-    let mut constraints = sudoku_constraints();
+    let mut constraints = sudoku_constraints(IVec2::ZERO);
     given_constraints(
         &mut constraints,
         &vec![
@@ -139,13 +280,15 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
             start: IVec2::new(0, 0),
             end: IVec2::new(0, 1),
         },
-        sum: 7,
+        sum: Some(7),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(1, 0), IVec2::new(1, 1), IVec2::new(2, 0)],
         },
-        sum: 16,
+        sum: Some(16),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::ManyBox {
@@ -154,75 +297,86 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
                 (IVec2::new(2, 1), IVec2::new(2, 1)),
             ],
         },
-        sum: 27,
+        sum: Some(27),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(5, 0),
             end: IVec2::new(5, 2),
         },
-        sum: 9,
+        sum: Some(9),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(6, 0),
             end: IVec2::new(8, 0),
         },
-        sum: 18,
+        sum: Some(18),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(6, 1),
             end: IVec2::new(7, 1),
         },
-        sum: 6,
+        sum: Some(6),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(8, 1),
             end: IVec2::new(8, 2),
         },
-        sum: 10,
+        sum: Some(10),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(0, 2)],
         },
-        sum: 4,
+        sum: Some(4),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(1, 2),
             end: IVec2::new(2, 2),
         },
-        sum: 10,
+        sum: Some(10),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(3, 2),
             end: IVec2::new(4, 2),
         },
-        sum: 17,
+        sum: Some(17),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(6, 2), IVec2::new(6, 3), IVec2::new(7, 2)],
         },
-        sum: 19,
+        sum: Some(19),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(0, 3),
             end: IVec2::new(1, 3),
         },
-        sum: 10,
+        sum: Some(10),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(2, 3),
             end: IVec2::new(3, 3),
         },
-        sum: 10,
+        sum: Some(10),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::ManyBox {
@@ -231,14 +385,16 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
                 (IVec2::new(5, 4), IVec2::new(5, 5)),
             ],
         },
-        sum: 29,
+        sum: Some(29),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(7, 3),
             end: IVec2::new(8, 3),
         },
-        sum: 5,
+        sum: Some(5),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::ManyBox {
@@ -247,60 +403,69 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
                 (IVec2::new(1, 4), IVec2::new(1, 6)),
             ],
         },
-        sum: 25,
+        sum: Some(25),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(2, 4),
             end: IVec2::new(2, 5),
         },
-        sum: 6,
+        sum: Some(6),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(3, 4),
             end: IVec2::new(4, 4),
         },
-        sum: 6,
+        sum: Some(6),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(6, 4), IVec2::new(7, 4), IVec2::new(7, 5)],
         },
-        sum: 14,
+        sum: Some(14),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(8, 4)],
         },
-        sum: 6,
+        sum: Some(6),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(3, 5),
             end: IVec2::new(4, 5),
         },
-        sum: 9,
+        sum: Some(9),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(6, 5),
             end: IVec2::new(6, 6),
         },
-        sum: 16,
+        sum: Some(16),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(8, 5),
             end: IVec2::new(8, 6),
         },
-        sum: 9,
+        sum: Some(9),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(0, 6)],
         },
-        sum: 1,
+        sum: Some(1),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::ManyBox {
@@ -309,31 +474,36 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
                 (IVec2::new(1, 7), IVec2::new(3, 7)),
             ],
         },
-        sum: 21,
+        sum: Some(21),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(3, 6), IVec2::new(4, 6), IVec2::new(4, 7)],
         },
-        sum: 9,
+        sum: Some(9),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(5, 6)],
         },
-        sum: 7,
+        sum: Some(7),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(7, 6), IVec2::new(7, 7), IVec2::new(8, 7)],
         },
-        sum: 20,
+        sum: Some(20),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(0, 7)],
         },
-        sum: 9,
+        sum: Some(9),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::ManyBox {
@@ -342,39 +512,118 @@ pub fn killer_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
                 (IVec2::new(3, 8), IVec2::new(4, 8)),
             ],
         },
-        sum: 21,
+        sum: Some(21),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(6, 7),
             end: IVec2::new(6, 8),
         },
-        sum: 8,
+        sum: Some(8),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Many {
             cells: vec![IVec2::new(0, 8)],
         },
-        sum: 7,
+        sum: Some(7),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(1, 8),
             end: IVec2::new(2, 8),
         },
-        sum: 10,
+        sum: Some(10),
+        allow_repeats: false,
     });
     constraints.push(ConstraintSpecifier::Killer {
         region: RegionSpecifier::Box {
             start: IVec2::new(7, 8),
             end: IVec2::new(8, 8),
         },
-        sum: 4,
+        sum: Some(4),
+        allow_repeats: false,
     });
     let specifier = SudokuSpecifier {
         size: IVec2::new(9, 9),
         numbers: vec![IVec2::new(1, 9)],
         constraints,
+        domains: Vec::new(),
+        inactive: Vec::new(),
+    };
+    if let Some(path) = path {
+        specifier.to_file(path, true).unwrap();
+    }
+    specifier
+}
+
+pub fn kenken_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
+    let mut specifier = latin_square(4);
+    let cages = vec![
+        (
+            vec![IVec2::new(0, 0), IVec2::new(0, 1)],
+            Operation::Subtract,
+            2,
+        ),
+        (
+            vec![IVec2::new(1, 0), IVec2::new(2, 0)],
+            Operation::Multiply,
+            6,
+        ),
+        (
+            vec![IVec2::new(3, 0), IVec2::new(3, 1)],
+            Operation::Divide,
+            2,
+        ),
+        (
+            vec![IVec2::new(1, 1), IVec2::new(2, 1)],
+            Operation::Subtract,
+            3,
+        ),
+        (
+            vec![IVec2::new(0, 2), IVec2::new(0, 3), IVec2::new(1, 3)],
+            Operation::Add,
+            7,
+        ),
+        (
+            vec![IVec2::new(1, 2), IVec2::new(2, 2)],
+            Operation::Multiply,
+            6,
+        ),
+        (
+            vec![IVec2::new(3, 2), IVec2::new(3, 3), IVec2::new(2, 3)],
+            Operation::Multiply,
+            12,
+        ),
+    ];
+    for (cells, operation, target) in cages {
+        specifier.constraints.push(ConstraintSpecifier::Arithmetic {
+            region: RegionSpecifier::Many { cells },
+            operation,
+            target,
+        });
+    }
+    if let Some(path) = path {
+        specifier.to_file(path, true).unwrap();
+    }
+    specifier
+}
+
+pub fn magic_square_test_model(path: Option<&PathBuf>) -> SudokuSpecifier {
+    let mut constraints = sudoku_constraints(IVec2::ZERO);
+    constraints.extend(magic_square_constraints(IVec2::new(3, 3), Some(15)));
+    given_constraints(
+        &mut constraints,
+        &vec![(1, vec![IVec2::new(0, 0)]), (2, vec![IVec2::new(3, 3)])],
+    );
+    let specifier = SudokuSpecifier {
+        size: IVec2::new(9, 9),
+        numbers: vec![IVec2::new(1, 9)],
+        constraints,
+        domains: Vec::new(),
+        inactive: Vec::new(),
     };
     if let Some(path) = path {
         specifier.to_file(path, true).unwrap();
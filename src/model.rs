@@ -2,6 +2,7 @@ pub mod constraint;
 pub mod region;
 
 use crate::model::constraint::{Constraint, ConstraintSpecifier};
+use crate::model::region::{Region, RegionSpecifier};
 use glam::IVec2;
 use crate::Try;
 use serde::{Deserialize, Serialize};
@@ -13,6 +14,25 @@ pub struct SudokuSpecifier {
     pub size: IVec2,
     pub numbers: Vec<IVec2>,
     pub constraints: Vec<ConstraintSpecifier>,
+    #[serde(default)]
+    pub domains: Vec<DomainSpecifier>,
+    /// Cells that are not part of the puzzle, like the gaps between the grids of a samurai.
+    #[serde(default)]
+    pub inactive: Vec<RegionSpecifier>,
+}
+
+/// Replaces the numbers allowed in a region. Later domains override earlier ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainSpecifier {
+    pub region: RegionSpecifier,
+    pub numbers: Vec<IVec2>,
+}
+
+fn expand_numbers(ranges: &[IVec2]) -> Vec<i32> {
+    let number_set: HashSet<i32> = ranges.iter().flat_map(|v| v.x..=v.y).collect();
+    let mut numbers = number_set.into_iter().collect::<Vec<i32>>();
+    numbers.sort_unstable();
+    numbers
 }
 
 impl SudokuSpecifier {
@@ -33,29 +53,64 @@ impl SudokuSpecifier {
     }
 
     pub fn build_model(&self) -> SudokuModel {
-        let number_set: HashSet<i32> = self.numbers.iter().flat_map(|v| v.x..=v.y).collect();
-        let mut numbers = number_set.into_iter().collect::<Vec<i32>>();
-        numbers.sort_unstable();
+        let default_numbers = expand_numbers(&self.numbers);
+        let mut domains = HashMap::new();
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                domains.insert(IVec2::new(x, y), default_numbers.clone());
+            }
+        }
+        for domain in &self.domains {
+            let numbers = expand_numbers(&domain.numbers);
+            for cell in domain.region.build_region().cells {
+                domains.insert(cell, numbers.clone());
+            }
+        }
+        let numbers = expand_numbers(
+            &self
+                .numbers
+                .iter()
+                .chain(self.domains.iter().flat_map(|domain| &domain.numbers))
+                .copied()
+                .collect::<Vec<_>>(),
+        );
+        let inactive: HashSet<IVec2> = self
+            .inactive
+            .iter()
+            .flat_map(|region| region.build_region().cells)
+            .collect();
         let constraints = self
             .constraints
             .iter()
-            .map(|c| c.build_constraint())
+            .map(|c| c.build_constraint(&self.size).mask(&inactive))
             .collect();
         let number_indices = numbers.iter().enumerate().map(|(i, &n)| (n, i)).collect();
-        SudokuModel {
-            size: self.size.clone(),
+        let mut model = SudokuModel {
+            size: self.size,
             numbers,
             number_indices,
+            domains,
+            inactive,
             constraints,
-        }
+        };
+        model.constraints = model
+            .constraints
+            .iter()
+            .map(|c| c.resolve(&model))
+            .collect();
+        model
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct SudokuModel {
     pub size: IVec2,
+    /// Every number that any cell may hold.
     pub numbers: Vec<i32>,
     pub number_indices: HashMap<i32, usize>,
+    /// The numbers each cell may hold.
+    pub domains: HashMap<IVec2, Vec<i32>>,
+    pub inactive: HashSet<IVec2>,
     pub constraints: Vec<Constraint>,
 }
 
@@ -65,4 +120,51 @@ impl SudokuModel {
         specifier.to_file(path, true)?;
         Ok(specifier.build_model())
     }
+
+    /// Unique regions laid out like the boxes of a sudoku: full rectangles spanning more than one
+    /// row and column, holding one of each possible number, aligned to a tiling of their own
+    /// shape and not overlapping any other box.
+    pub fn box_regions(&self) -> Vec<&Region> {
+        let mut boxes: Vec<&Region> = Vec::new();
+        let uniques = self
+            .constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                Constraint::Unique(region) => Some(region),
+                _ => None,
+            });
+        for region in uniques {
+            if self.is_box(region) && !boxes.iter().any(|b| b.cells == region.cells) {
+                boxes.push(region);
+            }
+        }
+        boxes
+            .iter()
+            .filter(|region| {
+                !boxes.iter().any(|other| {
+                    other.cells != region.cells && !other.cells.is_disjoint(&region.cells)
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    fn is_box(&self, region: &Region) -> bool {
+        let cells = region.cells.iter().copied();
+        let (Some(min), Some(max)) = (cells.clone().reduce(IVec2::min), cells.reduce(IVec2::max))
+        else {
+            return false;
+        };
+        let shape = max - min + IVec2::ONE;
+        let numbers: HashSet<i32> = region
+            .cells
+            .iter()
+            .filter_map(|cell| self.domains.get(cell))
+            .flatten()
+            .copied()
+            .collect();
+        let full = (shape.x * shape.y) as usize == region.cells.len();
+        let aligned = min.rem_euclid(shape) == IVec2::ZERO;
+        shape.x > 1 && shape.y > 1 && full && aligned && region.cells.len() == numbers.len()
+    }
 }
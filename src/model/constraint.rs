@@ -1,6 +1,10 @@
-use crate::model::region::{Line, LineSpecifier, Region, RegionSpecifier};
+use crate::model::region::{
+    build_ray, in_grid, inward_direction, Line, LineSpecifier, Region, RegionSpecifier,
+};
+use crate::model::SudokuModel;
 use glam::IVec2;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "constraint_type")]
@@ -10,14 +14,120 @@ pub enum ConstraintSpecifier {
     },
     Thermometer {
         line: LineSpecifier,
+        #[serde(default)]
+        strictness: Strictness,
     },
-    Killer {
+    Whisper {
+        line: LineSpecifier,
+        difference: i32,
+    },
+    Renban {
+        line: LineSpecifier,
+    },
+    Palindrome {
+        line: LineSpecifier,
+    },
+    Clone {
         region: RegionSpecifier,
+        offset: IVec2,
+    },
+    /// Each cell equals the cell at the same index of `clone`, which allows rotated or reflected
+    /// clones.
+    CloneCells {
+        cells: Vec<IVec2>,
+        clone: Vec<IVec2>,
+    },
+    Between {
+        line: LineSpecifier,
+    },
+    Lockout {
+        line: LineSpecifier,
+        threshold: i32,
+    },
+    /// Every three consecutive cells hold one low, one middle and one high number.
+    Entropic {
+        line: LineSpecifier,
+    },
+    /// Every three consecutive cells hold one number from each residue class modulo 3.
+    Modular {
+        line: LineSpecifier,
+    },
+    /// Adjacent cells alternate between even and odd numbers.
+    Parity {
+        line: LineSpecifier,
+    },
+    RegionSum {
+        line: LineSpecifier,
+    },
+    /// Cells the same distance from the centre of the line sum to the centre cell, or to a shared
+    /// total on lines with no centre cell.
+    Zipper {
+        line: LineSpecifier,
+    },
+    /// The line splits into consecutive segments that each sum to `sum`.
+    SegmentSum {
+        line: LineSpecifier,
         sum: i32,
     },
+    LittleKiller {
+        position: IVec2,
+        direction: IVec2,
+        sum: i32,
+    },
+    Sandwich {
+        position: IVec2,
+        sum: i32,
+        #[serde(default)]
+        markers: Option<(i32, i32)>,
+    },
+    Skyscraper {
+        position: IVec2,
+        count: usize,
+    },
+    XSum {
+        position: IVec2,
+        sum: i32,
+    },
+    AntiMove {
+        movement: Movement,
+    },
+    /// Pairs of orthogonal neighbours without a marker cannot have any of the given
+    /// relationships. By default, every marker kind used in the puzzle is negated.
+    Negative {
+        #[serde(default)]
+        relationships: Vec<Relationship>,
+    },
+    /// Cells in the same position of different boxes are unique. Boxes must be rectangles of the
+    /// same shape. Without explicit boxes, the boxes of the puzzle are used, which only works for
+    /// a single grid: the boxes of overlapping grids would all merge into the same groups.
+    DisjointGroups {
+        #[serde(default)]
+        boxes: Vec<RegionSpecifier>,
+    },
+    /// A cell's value names the column of `digit` in the same row.
+    Indexing {
+        region: RegionSpecifier,
+        digit: i32,
+    },
+    Quadruple {
+        corner: IVec2,
+        digits: Vec<i32>,
+    },
+    Killer {
+        region: RegionSpecifier,
+        #[serde(default)]
+        sum: Option<i32>,
+        #[serde(default)]
+        allow_repeats: bool,
+    },
+    Arithmetic {
+        region: RegionSpecifier,
+        operation: Operation,
+        target: i32,
+    },
     Arrow {
         region: RegionSpecifier,
-        tail: IVec2,
+        tail: ArrowTail,
     },
     Relationship {
         first: IVec2,
@@ -28,9 +138,38 @@ pub enum ConstraintSpecifier {
         region: RegionSpecifier,
         property: Property,
     },
+    /// The sum of each cell multiplied by its weight, compared to `constant`.
+    Linear {
+        terms: Vec<(IVec2, i32)>,
+        comparison: Comparison,
+        constant: i32,
+    },
+    /// Each digit appears in the region as many times as its count allows.
+    Cardinality {
+        region: RegionSpecifier,
+        counts: Vec<DigitCount>,
+    },
+}
+
+/// How often a digit may appear. A count without a digit applies to every digit not listed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigitCount {
+    #[serde(default)]
+    pub digit: Option<i32>,
+    pub min: usize,
+    pub max: usize,
 }
 
+/// The circle of an arrow. A pill spans several cells that are read as one number, most
+/// significant digit first.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArrowTail {
+    Cell(IVec2),
+    Pill(Vec<IVec2>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Relationship {
     Greater,
@@ -39,6 +178,87 @@ pub enum Relationship {
     NotEqual,
     Consecutive,
     Double,
+    Sum(i32),
+    Difference(i32),
+    Ratio(i32),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strictness {
+    /// Each cell is greater than the previous one.
+    #[default]
+    Strict,
+    /// Each cell is greater than or equal to the previous one.
+    Slow,
+    /// Each cell is at least the given amount greater than the previous one.
+    MinStep(i32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Add,
+    /// The largest digit minus all others.
+    Subtract,
+    Multiply,
+    /// The largest digit divided by all others.
+    Divide,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Movement {
+    Knight,
+    King,
+    /// The given offsets in every rotation and reflection.
+    Custom(Vec<IVec2>),
+}
+
+impl Relationship {
+    /// Whether the relationship is drawn as a dot or XV marker, which are the kinds a negative
+    /// constraint negates by default.
+    pub fn is_marker(&self) -> bool {
+        matches!(
+            self,
+            Relationship::Consecutive
+                | Relationship::Double
+                | Relationship::Sum(_)
+                | Relationship::Difference(_)
+                | Relationship::Ratio(_)
+        )
+    }
+}
+
+impl Movement {
+    pub fn offsets(&self) -> Vec<IVec2> {
+        let base = match self {
+            Movement::Knight => vec![IVec2::new(1, 2)],
+            Movement::King => vec![IVec2::new(1, 0), IVec2::new(1, 1)],
+            Movement::Custom(offsets) => offsets.clone(),
+        };
+        let mut offsets = Vec::new();
+        for offset in base {
+            for (x, y) in [(offset.x, offset.y), (offset.y, offset.x)] {
+                for (sign_x, sign_y) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    let offset = IVec2::new(x * sign_x, y * sign_y);
+                    if offset != IVec2::ZERO && !offsets.contains(&offset) {
+                        offsets.push(offset);
+                    }
+                }
+            }
+        }
+        offsets
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,19 +267,100 @@ pub enum Property {
     Even,
     Odd,
     Given(i32),
+    OneOf(Vec<i32>),
+    NoneOf(Vec<i32>),
+    Range {
+        min: i32,
+        max: i32,
+    },
+    /// Values congruent to `remainder` modulo `modulus`, a generalised parity.
+    Residue {
+        modulus: i32,
+        remainder: i32,
+    },
+    /// The lower half of the number set, excluding the middle number of odd-sized sets.
+    Low,
+    /// The upper half of the number set, excluding the middle number of odd-sized sets.
+    High,
 }
 
 #[derive(Debug, Clone)]
 pub enum Constraint {
     Unique(Region),
-    Thermometer(Line),
+    Thermometer {
+        line: Line,
+        strictness: Strictness,
+    },
+    Whisper {
+        line: Line,
+        difference: i32,
+    },
+    Renban(Line),
+    Equality(Vec<(IVec2, IVec2)>),
+    Between(Line),
+    Lockout {
+        line: Line,
+        threshold: i32,
+    },
+    Entropic(Line),
+    Modular(Line),
+    Parity(Line),
+    RegionSum {
+        line: Line,
+        segments: Vec<Vec<IVec2>>,
+    },
+    Zipper(Line),
+    SegmentSum {
+        line: Line,
+        sum: i32,
+    },
+    LittleKiller {
+        line: Line,
+        sum: i32,
+    },
+    Sandwich {
+        line: Line,
+        sum: i32,
+        markers: Option<(i32, i32)>,
+    },
+    Skyscraper {
+        line: Line,
+        count: usize,
+    },
+    XSum {
+        line: Line,
+        sum: i32,
+    },
+    AntiMove {
+        offsets: Vec<IVec2>,
+    },
+    Negative {
+        relationships: Vec<Relationship>,
+        /// Each adjacent pair with the relationships it cannot have.
+        pairs: Vec<(IVec2, IVec2, Vec<Relationship>)>,
+    },
+    DisjointGroups(Vec<Region>),
+    Indexing {
+        region: Region,
+        digit: i32,
+    },
+    Quadruple {
+        cells: Vec<IVec2>,
+        digits: Vec<i32>,
+    },
     Killer {
         region: Region,
-        sum: i32,
+        sum: Option<i32>,
+        allow_repeats: bool,
+    },
+    Arithmetic {
+        region: Region,
+        operation: Operation,
+        target: i32,
     },
     Arrow {
         region: Region,
-        tail: IVec2,
+        tail: Vec<IVec2>,
     },
     Relationship {
         first: IVec2,
@@ -70,21 +371,189 @@ pub enum Constraint {
         region: Region,
         property: Property,
     },
+    Linear {
+        terms: Vec<(IVec2, i32)>,
+        comparison: Comparison,
+        constant: i32,
+    },
+    Cardinality {
+        region: Region,
+        counts: Vec<DigitCount>,
+    },
 }
 
 impl ConstraintSpecifier {
-    pub fn build_constraint(&self) -> Constraint {
+    pub fn build_constraint(&self, size: &IVec2) -> Constraint {
         match self {
             ConstraintSpecifier::Unique { region } => Constraint::Unique(region.build_region()),
-            ConstraintSpecifier::Thermometer { line } => Constraint::Thermometer(line.build_line()),
-            ConstraintSpecifier::Killer { region, sum } => Constraint::Killer {
+            ConstraintSpecifier::Thermometer { line, strictness } => {
+                if let Strictness::MinStep(step) = strictness {
+                    if *step < 1 {
+                        panic!("Thermometer minimum step must be positive");
+                    }
+                }
+                Constraint::Thermometer {
+                    line: line.build_line(),
+                    strictness: strictness.clone(),
+                }
+            }
+            ConstraintSpecifier::Whisper { line, difference } => Constraint::Whisper {
+                line: line.build_line(),
+                difference: *difference,
+            },
+            ConstraintSpecifier::Renban { line } => Constraint::Renban(line.build_line()),
+            ConstraintSpecifier::Palindrome { line } => {
+                let cells = line.build_line().cells;
+                let pairs = (0..cells.len() / 2)
+                    .map(|i| (cells[i], cells[cells.len() - 1 - i]))
+                    .collect();
+                Constraint::Equality(pairs)
+            }
+            ConstraintSpecifier::Clone { region, offset } => {
+                let pairs = region
+                    .build_region()
+                    .cells
+                    .into_iter()
+                    .map(|cell| (cell, cell + *offset))
+                    .collect::<Vec<_>>();
+                if pairs.iter().any(|(_, clone)| !in_grid(clone, size)) {
+                    panic!("Clone offset must keep every cell inside the grid");
+                }
+                Constraint::Equality(pairs)
+            }
+            ConstraintSpecifier::CloneCells { cells, clone } => {
+                if cells.len() != clone.len() {
+                    panic!("Clone must have as many cells as the original");
+                }
+                if cells.iter().chain(clone).any(|cell| !in_grid(cell, size)) {
+                    panic!("Clone cells must be inside the grid");
+                }
+                Constraint::Equality(cells.iter().copied().zip(clone.iter().copied()).collect())
+            }
+            ConstraintSpecifier::Between { line } => Constraint::Between(line.build_line()),
+            ConstraintSpecifier::Lockout { line, threshold } => Constraint::Lockout {
+                line: line.build_line(),
+                threshold: *threshold,
+            },
+            ConstraintSpecifier::Entropic { line } => Constraint::Entropic(line.build_line()),
+            ConstraintSpecifier::Modular { line } => Constraint::Modular(line.build_line()),
+            ConstraintSpecifier::Parity { line } => Constraint::Parity(line.build_line()),
+            ConstraintSpecifier::RegionSum { line } => Constraint::RegionSum {
+                line: line.build_line(),
+                segments: Vec::new(),
+            },
+            ConstraintSpecifier::Zipper { line } => Constraint::Zipper(line.build_line()),
+            ConstraintSpecifier::SegmentSum { line, sum } => Constraint::SegmentSum {
+                line: line.build_line(),
+                sum: *sum,
+            },
+            ConstraintSpecifier::LittleKiller {
+                position,
+                direction,
+                sum,
+            } => {
+                if direction.x.abs() != 1 || direction.y.abs() != 1 {
+                    panic!("Little killer must point along a diagonal");
+                }
+                let line = build_ray(position, direction, size);
+                if in_grid(position, size) || line.cells.is_empty() {
+                    panic!("Little killer must point into the grid from outside");
+                }
+                Constraint::LittleKiller { line, sum: *sum }
+            }
+            ConstraintSpecifier::Sandwich {
+                position,
+                sum,
+                markers,
+            } => {
+                let Some(direction) = inward_direction(position, size) else {
+                    panic!("Sandwich clue must be next to a row or column");
+                };
+                if markers.is_some_and(|(low, high)| low == high) {
+                    panic!("Sandwich markers must be different numbers");
+                }
+                Constraint::Sandwich {
+                    line: build_ray(position, &direction, size),
+                    sum: *sum,
+                    markers: *markers,
+                }
+            }
+            ConstraintSpecifier::Skyscraper { position, count } => {
+                let Some(direction) = inward_direction(position, size) else {
+                    panic!("Skyscraper clue must be next to a row or column");
+                };
+                Constraint::Skyscraper {
+                    line: build_ray(position, &direction, size),
+                    count: *count,
+                }
+            }
+            ConstraintSpecifier::XSum { position, sum } => {
+                let Some(direction) = inward_direction(position, size) else {
+                    panic!("X-sum clue must be next to a row or column");
+                };
+                Constraint::XSum {
+                    line: build_ray(position, &direction, size),
+                    sum: *sum,
+                }
+            }
+            ConstraintSpecifier::AntiMove { movement } => Constraint::AntiMove {
+                offsets: movement.offsets(),
+            },
+            ConstraintSpecifier::Negative { relationships } => Constraint::Negative {
+                relationships: relationships.clone(),
+                pairs: Vec::new(),
+            },
+            ConstraintSpecifier::DisjointGroups { boxes } => {
+                let boxes = boxes.iter().map(|b| b.build_region()).collect::<Vec<_>>();
+                Constraint::DisjointGroups(disjoint_groups(&boxes.iter().collect::<Vec<_>>()))
+            }
+            ConstraintSpecifier::Indexing { region, digit } => Constraint::Indexing {
+                region: region.build_region(),
+                digit: *digit,
+            },
+            ConstraintSpecifier::Quadruple { corner, digits } => {
+                if digits.len() > 4 {
+                    panic!("Quadruple cannot have more than four digits");
+                }
+                if !in_grid(corner, size) || !in_grid(&(*corner + IVec2::ONE), size) {
+                    panic!("Quadruple must sit on a corner inside the grid");
+                }
+                let cells = [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE]
+                    .iter()
+                    .map(|offset| *corner + *offset)
+                    .collect();
+                Constraint::Quadruple {
+                    cells,
+                    digits: digits.clone(),
+                }
+            }
+            ConstraintSpecifier::Killer {
+                region,
+                sum,
+                allow_repeats,
+            } => Constraint::Killer {
                 region: region.build_region(),
                 sum: *sum,
+                allow_repeats: *allow_repeats,
             },
-            ConstraintSpecifier::Arrow { region, tail } => Constraint::Arrow {
+            ConstraintSpecifier::Arithmetic {
+                region,
+                operation,
+                target,
+            } => Constraint::Arithmetic {
                 region: region.build_region(),
-                tail: tail.clone(),
+                operation: operation.clone(),
+                target: *target,
             },
+            ConstraintSpecifier::Arrow { region, tail } => {
+                let tail = match tail {
+                    ArrowTail::Cell(cell) => vec![*cell],
+                    ArrowTail::Pill(cells) => cells.clone(),
+                };
+                let mut region = region.build_region();
+                region.cells.retain(|cell| !tail.contains(cell));
+                Constraint::Arrow { region, tail }
+            }
             ConstraintSpecifier::Relationship {
                 first,
                 second,
@@ -94,15 +563,283 @@ impl ConstraintSpecifier {
                     panic!("Relationship cannot be between the same cell");
                 }
                 Constraint::Relationship {
-                    first: first.clone(),
-                    second: second.clone(),
+                    first: *first,
+                    second: *second,
                     relationship: relationship.clone(),
                 }
             }
-            ConstraintSpecifier::Property { region, property } => Constraint::Property {
+            ConstraintSpecifier::Property { region, property } => {
+                if let Property::Residue { modulus, .. } = property {
+                    if *modulus <= 0 {
+                        panic!("Residue modulus must be positive");
+                    }
+                }
+                Constraint::Property {
+                    region: region.build_region(),
+                    property: property.clone(),
+                }
+            }
+            ConstraintSpecifier::Linear {
+                terms,
+                comparison,
+                constant,
+            } => Constraint::Linear {
+                terms: terms.clone(),
+                comparison: comparison.clone(),
+                constant: *constant,
+            },
+            ConstraintSpecifier::Cardinality { region, counts } => Constraint::Cardinality {
                 region: region.build_region(),
+                counts: counts.clone(),
+            },
+        }
+    }
+}
+
+impl Constraint {
+    /// Fills in the parts of a constraint that depend on the rest of the model.
+    pub fn resolve(&self, model: &SudokuModel) -> Constraint {
+        match self {
+            Constraint::RegionSum { line, .. } => {
+                let boxes = model.box_regions();
+                let mut segments: Vec<Vec<IVec2>> = Vec::new();
+                let mut current_box = None;
+                for cell in &line.cells {
+                    let cell_box = boxes.iter().position(|b| b.cells.contains(cell));
+                    match segments.last_mut() {
+                        Some(segment) if cell_box == current_box => segment.push(*cell),
+                        _ => segments.push(vec![*cell]),
+                    }
+                    current_box = cell_box;
+                }
+                Constraint::RegionSum {
+                    line: line.clone(),
+                    segments,
+                }
+            }
+            Constraint::Negative { relationships, .. } => {
+                let marked = model
+                    .constraints
+                    .iter()
+                    .filter_map(|constraint| match constraint {
+                        Constraint::Relationship {
+                            first,
+                            second,
+                            relationship,
+                        } => Some((*first, *second, relationship)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                let mut relationships = relationships.clone();
+                if relationships.is_empty() {
+                    for (_, _, relationship) in &marked {
+                        if relationship.is_marker() && !relationships.contains(relationship) {
+                            relationships.push((*relationship).clone());
+                        }
+                    }
+                }
+                let is_marked = |a: IVec2, b: IVec2, kind: &Relationship| {
+                    marked.iter().any(|(first, second, relationship)| {
+                        let same_cells =
+                            (*first == a && *second == b) || (*first == b && *second == a);
+                        same_cells && *relationship == kind
+                    })
+                };
+                let mut pairs = Vec::new();
+                for y in 0..model.size.y {
+                    for x in 0..model.size.x {
+                        let cell = IVec2::new(x, y);
+                        for neighbor in [cell + IVec2::X, cell + IVec2::Y] {
+                            let active = !model.inactive.contains(&cell)
+                                && !model.inactive.contains(&neighbor);
+                            if !in_grid(&neighbor, &model.size) || !active {
+                                continue;
+                            }
+                            let negated = relationships
+                                .iter()
+                                .filter(|kind| !is_marked(cell, neighbor, kind))
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            if !negated.is_empty() {
+                                pairs.push((cell, neighbor, negated));
+                            }
+                        }
+                    }
+                }
+                Constraint::Negative {
+                    relationships,
+                    pairs,
+                }
+            }
+            Constraint::DisjointGroups(groups) if groups.is_empty() => {
+                let boxes = model.box_regions();
+                if boxes.is_empty() {
+                    panic!("Disjoint groups need boxes, but the puzzle has none");
+                }
+                let groups = disjoint_groups(&boxes);
+                if groups.iter().any(|g| g.cells.len() > model.numbers.len()) {
+                    panic!("Disjoint groups on several grids need their boxes listed explicitly");
+                }
+                Constraint::DisjointGroups(groups)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Removes inactive cells from the regions of a constraint and stops the rays of outside
+    /// clues at the first inactive cell. Drawn lines and single cells cannot be inactive.
+    pub fn mask(&self, inactive: &HashSet<IVec2>) -> Constraint {
+        let active = |region: &Region| Region {
+            cells: region.cells.difference(inactive).copied().collect(),
+        };
+        let ray = |line: &Line| {
+            if line
+                .cells
+                .first()
+                .is_some_and(|cell| inactive.contains(cell))
+            {
+                panic!("Outside clue must point at an active cell");
+            }
+            Line {
+                cells: line
+                    .cells
+                    .iter()
+                    .take_while(|cell| !inactive.contains(cell))
+                    .copied()
+                    .collect(),
+            }
+        };
+        let crosses = |cells: &[IVec2]| cells.iter().any(|cell| inactive.contains(cell));
+        match self {
+            Constraint::Thermometer { line, .. }
+            | Constraint::Whisper { line, .. }
+            | Constraint::Renban(line)
+            | Constraint::Between(line)
+            | Constraint::Lockout { line, .. }
+            | Constraint::Entropic(line)
+            | Constraint::Modular(line)
+            | Constraint::Parity(line)
+            | Constraint::RegionSum { line, .. }
+            | Constraint::Zipper(line)
+            | Constraint::SegmentSum { line, .. }
+                if crosses(&line.cells) =>
+            {
+                panic!("Lines cannot pass through inactive cells");
+            }
+            Constraint::Relationship { first, second, .. } if crosses(&[*first, *second]) => {
+                panic!("Relationship cannot use an inactive cell");
+            }
+            Constraint::Linear { terms, .. }
+                if crosses(&terms.iter().map(|(cell, _)| *cell).collect::<Vec<_>>()) =>
+            {
+                panic!("Linear constraint cannot use an inactive cell");
+            }
+            Constraint::LittleKiller { line, sum } => Constraint::LittleKiller {
+                line: ray(line),
+                sum: *sum,
+            },
+            Constraint::Sandwich { line, sum, markers } => Constraint::Sandwich {
+                line: ray(line),
+                sum: *sum,
+                markers: *markers,
+            },
+            Constraint::Skyscraper { line, count } => Constraint::Skyscraper {
+                line: ray(line),
+                count: *count,
+            },
+            Constraint::XSum { line, sum } => Constraint::XSum {
+                line: ray(line),
+                sum: *sum,
+            },
+            Constraint::Quadruple { cells, digits } => Constraint::Quadruple {
+                cells: cells
+                    .iter()
+                    .filter(|cell| !inactive.contains(cell))
+                    .copied()
+                    .collect(),
+                digits: digits.clone(),
+            },
+            Constraint::Unique(region) => Constraint::Unique(active(region)),
+            Constraint::Equality(pairs) => Constraint::Equality(
+                pairs
+                    .iter()
+                    .filter(|(a, b)| !inactive.contains(a) && !inactive.contains(b))
+                    .copied()
+                    .collect(),
+            ),
+            Constraint::DisjointGroups(groups) => {
+                Constraint::DisjointGroups(groups.iter().map(active).collect())
+            }
+            Constraint::Indexing { region, digit } => Constraint::Indexing {
+                region: active(region),
+                digit: *digit,
+            },
+            Constraint::Killer {
+                region,
+                sum,
+                allow_repeats,
+            } => Constraint::Killer {
+                region: active(region),
+                sum: *sum,
+                allow_repeats: *allow_repeats,
+            },
+            Constraint::Arithmetic {
+                region,
+                operation,
+                target,
+            } => Constraint::Arithmetic {
+                region: active(region),
+                operation: operation.clone(),
+                target: *target,
+            },
+            Constraint::Arrow { region, tail } => {
+                if crosses(tail) {
+                    panic!("Arrow circle cannot use an inactive cell");
+                }
+                Constraint::Arrow {
+                    region: active(region),
+                    tail: tail.clone(),
+                }
+            }
+            Constraint::Property { region, property } => Constraint::Property {
+                region: active(region),
                 property: property.clone(),
             },
+            Constraint::Cardinality { region, counts } => Constraint::Cardinality {
+                region: active(region),
+                counts: counts.clone(),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Groups the cells of each box by their position relative to the box's top left corner.
+fn disjoint_groups(boxes: &[&Region]) -> Vec<Region> {
+    let mut groups: HashMap<IVec2, Region> = HashMap::new();
+    let mut box_shape = None;
+    for region in boxes {
+        let cells = region.cells.iter().copied();
+        let (Some(corner), Some(max)) =
+            (cells.clone().reduce(IVec2::min), cells.reduce(IVec2::max))
+        else {
+            continue;
+        };
+        let shape = max - corner + IVec2::ONE;
+        let full = (shape.x * shape.y) as usize == region.cells.len();
+        if !full || box_shape.is_some_and(|box_shape| box_shape != shape) {
+            panic!("Disjoint groups need boxes that are rectangles of the same shape");
+        }
+        box_shape = Some(shape);
+        for cell in &region.cells {
+            groups
+                .entry(*cell - corner)
+                .or_insert_with(|| Region {
+                    cells: HashSet::new(),
+                })
+                .cells
+                .insert(*cell);
         }
     }
+    groups.into_values().collect()
 }
@@ -94,6 +94,32 @@ impl LineSpecifier {
     }
 }
 
+/// Collects the cells reached by stepping from `position` in `direction` until leaving the grid.
+pub fn build_ray(position: &IVec2, direction: &IVec2, size: &IVec2) -> Line {
+    let cells = (1..)
+        .map(|i| *position + *direction * i)
+        .take_while(|cell| in_grid(cell, size))
+        .collect();
+    Line { cells }
+}
+
+/// The orthogonal direction pointing into the grid from a position just outside of it.
+pub fn inward_direction(position: &IVec2, size: &IVec2) -> Option<IVec2> {
+    let in_row = (0..size.y).contains(&position.y);
+    let in_column = (0..size.x).contains(&position.x);
+    match (position.x, position.y) {
+        (-1, _) if in_row => Some(IVec2::new(1, 0)),
+        (x, _) if x == size.x && in_row => Some(IVec2::new(-1, 0)),
+        (_, -1) if in_column => Some(IVec2::new(0, 1)),
+        (_, y) if y == size.y && in_column => Some(IVec2::new(0, -1)),
+        _ => None,
+    }
+}
+
+pub fn in_grid(cell: &IVec2, size: &IVec2) -> bool {
+    cell.cmpge(IVec2::ZERO).all() && cell.cmplt(*size).all()
+}
+
 fn build_line(points: &[IVec2]) -> Vec<IVec2> {
     let mut cells = Vec::new();
     for (index, endpoints) in points.windows(2).enumerate() {
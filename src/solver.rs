@@ -1,5 +1,7 @@
-use crate::model::constraint::{Constraint, Property, Relationship};
-use crate::model::region::{Line, Region};
+use crate::model::constraint::{
+    Comparison, Constraint, DigitCount, Operation, Property, Relationship, Strictness,
+};
+use crate::model::region::{in_grid, Line, Region};
 use crate::model::SudokuModel;
 use glam::IVec2;
 use std::collections::{HashMap, HashSet};
@@ -35,7 +37,9 @@ impl<'a> SolverState<'a> {
     pub fn print_grid(&self) {
         for row in &self.grid {
             for cell in row {
-                if let Some(value) = cell.value {
+                if !cell.active {
+                    print!("  ");
+                } else if let Some(value) = cell.value {
                     print!("{} ", value);
                 } else {
                     print!(". ");
@@ -45,7 +49,7 @@ impl<'a> SolverState<'a> {
         }
         for row in &self.grid {
             for cell in row {
-                if cell.value.is_some() {
+                if !cell.active || cell.value.is_some() {
                     continue;
                 }
                 println!("{}: {:?}", cell.pos, cell.candidates);
@@ -53,7 +57,10 @@ impl<'a> SolverState<'a> {
         }
     }
     fn is_solved(&self) -> bool {
-        self.grid.iter().flatten().all(|cell| cell.value.is_some())
+        self.grid
+            .iter()
+            .flatten()
+            .all(|cell| !cell.active || cell.value.is_some())
     }
     fn get(&self, pos: &IVec2) -> &Cell {
         &self.grid[pos.y as usize][pos.x as usize]
@@ -68,6 +75,8 @@ pub struct Cell {
     pub pos: IVec2,
     pub value: Option<i32>,
     pub candidates: Vec<i32>,
+    /// Inactive cells are not part of the puzzle and never hold a value.
+    pub active: bool,
 }
 
 impl Cell {
@@ -84,20 +93,44 @@ impl Cell {
         }
         Some(self.candidates.len() != old_len)
     }
+    fn restrict<F>(&mut self, filter: F) -> Option<bool>
+    where
+        F: Fn(&i32) -> bool,
+    {
+        if let Some(value) = self.value {
+            return if filter(&value) { Some(false) } else { None };
+        }
+        self.limit(filter)
+    }
+    fn possible_values(&self) -> &[i32] {
+        match &self.value {
+            Some(value) => std::slice::from_ref(value),
+            None => &self.candidates,
+        }
+    }
     fn set_value(&mut self, value: i32) {
         self.value = Some(value);
         self.candidates.clear();
     }
 }
 
-fn empty_grid(size: &IVec2, candidates: &Vec<i32>) -> Vec<Vec<Cell>> {
-    (0..size.y)
+fn empty_grid(model: &SudokuModel) -> Vec<Vec<Cell>> {
+    (0..model.size.y)
         .map(|y| {
-            (0..size.x)
-                .map(|x| Cell {
-                    pos: IVec2::new(x, y),
-                    value: None,
-                    candidates: candidates.clone(),
+            (0..model.size.x)
+                .map(|x| {
+                    let pos = IVec2::new(x, y);
+                    let active = !model.inactive.contains(&pos);
+                    Cell {
+                        pos,
+                        value: None,
+                        candidates: if active {
+                            model.domains[&pos].clone()
+                        } else {
+                            Vec::new()
+                        },
+                        active,
+                    }
                 })
                 .collect()
         })
@@ -105,7 +138,7 @@ fn empty_grid(size: &IVec2, candidates: &Vec<i32>) -> Vec<Vec<Cell>> {
 }
 
 pub fn solve(model: SudokuModel) {
-    let grid = empty_grid(&model.size, &model.numbers);
+    let grid = empty_grid(&model);
     let precomputed = Precomputed::new(&model);
     let mut state = SolverState {
         grid,
@@ -128,7 +161,7 @@ fn bifurcate(model: &SudokuModel, state: &mut SolverState) -> Option<()> {
         .grid
         .iter()
         .flatten()
-        .filter(|cell| cell.value.is_none())
+        .filter(|cell| cell.active && cell.value.is_none())
         .min_by_key(|cell| cell.candidates.len())
         .unwrap();
     let pos = &lowest.pos;
@@ -149,7 +182,7 @@ fn try_limit(model: &SudokuModel, state: &mut SolverState) -> Option<()> {
     while changed {
         changed = false;
         for constraint in &model.constraints {
-            changed |= limit_state(&model, state, constraint)?;
+            changed |= limit_state(model, state, constraint)?;
         }
     }
     Some(())
@@ -165,11 +198,111 @@ fn limit_state(
         Constraint::Unique(region) => {
             limit_unique_clue(region, state, &mut changed)?;
         }
-        Constraint::Thermometer(line) => {
-            limit_thermometer_clue(line, model, state, &mut changed)?;
+        Constraint::Thermometer { line, strictness } => {
+            limit_thermometer_clue(line, strictness, model, state, &mut changed)?;
+        }
+        Constraint::Whisper { line, difference } => {
+            limit_whisper_clue(line, difference, state, &mut changed)?;
+        }
+        Constraint::Renban(line) => {
+            limit_renban_clue(line, model, state, &mut changed)?;
+        }
+        Constraint::Equality(pairs) => {
+            for (first, second) in pairs {
+                limit_pair(first, second, state, &mut changed, |a, b| a == b)?;
+            }
+        }
+        Constraint::Between(line) => {
+            limit_endpoint_line(
+                line,
+                state,
+                &mut changed,
+                |first, last| first != last,
+                |low, high, c| low < c && c < high,
+            )?;
+        }
+        Constraint::Lockout { line, threshold } => {
+            limit_endpoint_line(
+                line,
+                state,
+                &mut changed,
+                |first, last| (first - last).abs() >= *threshold,
+                |low, high, c| c < low || c > high,
+            )?;
+        }
+        Constraint::Entropic(line) => {
+            let count = model.numbers.len();
+            limit_class_line(
+                line,
+                3,
+                |c| model.number_indices[&c] * 3 / count,
+                state,
+                &mut changed,
+            )?;
+        }
+        Constraint::Modular(line) => {
+            limit_class_line(line, 3, |c| c.rem_euclid(3) as usize, state, &mut changed)?;
+        }
+        Constraint::Parity(line) => {
+            limit_class_line(line, 2, |c| c.rem_euclid(2) as usize, state, &mut changed)?;
+        }
+        Constraint::RegionSum { segments, .. } => {
+            limit_region_sum_clue(segments, state, &mut changed)?;
+        }
+        Constraint::Zipper(line) => {
+            limit_zipper_clue(line, state, &mut changed)?;
+        }
+        Constraint::SegmentSum { line, sum } => {
+            limit_segment_sum_clue(line, *sum, state, &mut changed)?;
+        }
+        Constraint::LittleKiller { line, sum } => {
+            limit_sum_bounds(&line.cells, *sum, *sum, state, &mut changed)?;
+        }
+        Constraint::Sandwich { line, sum, markers } => {
+            limit_sandwich_clue(line, *sum, markers, model, state, &mut changed)?;
+        }
+        Constraint::Skyscraper { line, count } => {
+            limit_skyscraper_clue(line, *count, state, &mut changed)?;
+        }
+        Constraint::XSum { line, sum } => {
+            limit_x_sum_clue(line, *sum, state, &mut changed)?;
+        }
+        Constraint::AntiMove { offsets } => {
+            limit_anti_move_clue(offsets, model, state, &mut changed)?;
+        }
+        Constraint::Negative { pairs, .. } => {
+            for (first, second, relationships) in pairs {
+                limit_pair(first, second, state, &mut changed, |a, b| {
+                    !relationships.iter().any(|r| relationship_holds(r, a, b))
+                })?;
+            }
+        }
+        Constraint::DisjointGroups(groups) => {
+            for group in groups {
+                limit_unique_clue(group, state, &mut changed)?;
+            }
+        }
+        Constraint::Indexing { region, digit } => {
+            limit_indexing_clue(region, *digit, model, state, &mut changed)?;
+        }
+        Constraint::Quadruple { cells, digits } => {
+            limit_quadruple_clue(cells, digits, state, &mut changed)?;
+        }
+        Constraint::Arrow { region, tail } => {
+            limit_arrow_clue(region, tail, state, &mut changed)?;
         }
         Constraint::Property { region, property } => {
-            limit_property_clue(region, state, property, &mut changed)?;
+            limit_property_clue(region, model, state, property, &mut changed)?;
+        }
+        Constraint::Linear {
+            terms,
+            comparison,
+            constant,
+        } => {
+            limit_linear_clue(terms, comparison, *constant, state, &mut changed)?;
+        }
+        Constraint::Cardinality { region, counts } => {
+            limit_cardinality_clue(region, counts, model, state, &mut changed)?;
         }
         Constraint::Relationship {
             first,
@@ -178,54 +311,176 @@ fn limit_state(
         } => {
             limit_relationship_clue(first, second, relationship, state, &mut changed)?;
         }
-        Constraint::Killer { region, sum } => {
-            limit_killer_clue(region, sum, state, &mut changed)?;
+        Constraint::Killer {
+            region,
+            sum,
+            allow_repeats,
+        } => {
+            limit_killer_clue(region, sum, *allow_repeats, state, &mut changed)?;
+        }
+        Constraint::Arithmetic {
+            region,
+            operation,
+            target,
+        } => {
+            limit_arithmetic_clue(region, operation, *target, state, &mut changed)?;
         }
-        _ => {}
     }
     Some(changed)
 }
 
 fn limit_killer_clue(
     region: &Region,
-    sum: &i32,
+    sum: &Option<i32>,
+    allow_repeats: bool,
     state: &mut SolverState,
     changed: &mut bool,
 ) -> Option<()> {
+    if !allow_repeats {
+        limit_unique_clue(region, state, changed)?;
+    }
+    let Some(sum) = sum else {
+        return Some(());
+    };
+    let cells = region.cells.iter().copied().collect::<Vec<_>>();
+    if allow_repeats {
+        return limit_sum_bounds(&cells, *sum, *sum, state, changed);
+    }
     let mut sum_so_far = 0;
-    let mut unknown_cells = Vec::new();
-    for pos in &region.cells {
+    let mut unknown_cells = 0;
+    for pos in &cells {
         let cell = state.get(pos);
         if let Some(value) = cell.value {
             sum_so_far += value;
         } else {
-            unknown_cells.push(pos);
+            unknown_cells += 1;
         }
     }
-    if sum_so_far > *sum {
+    let lowest_sum = state.precomputed.lowest_sums[unknown_cells];
+    let highest_sum = state.precomputed.highest_sums[unknown_cells];
+    if sum_so_far + highest_sum < *sum || sum_so_far + lowest_sum > *sum {
         return None;
     }
-    if sum_so_far == *sum {
-        return if !unknown_cells.is_empty() {
-            None
-        } else {
-            Some(())
-        };
+    let domains = cells
+        .iter()
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let (lows, highs) = remaining_bounds(&domains);
+    let supports = assignment_supports(&domains, true, |values| {
+        let total: i32 = values.iter().sum();
+        total + lows[values.len()] <= *sum && total + highs[values.len()] >= *sum
+    })?;
+    for (pos, support) in cells.iter().zip(supports) {
+        *changed |= state.get_mut(pos).restrict(|c| support.contains(c))?;
     }
-    let lowest_sum = state.precomputed.lowest_sums[unknown_cells.len()];
-    let highest_sum = state.precomputed.highest_sums[unknown_cells.len()];
-    if sum_so_far + highest_sum < *sum {
-        return None;
+    Some(())
+}
+
+fn limit_arithmetic_clue(
+    region: &Region,
+    operation: &Operation,
+    target: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let cells = region.cells.iter().copied().collect::<Vec<_>>();
+    let domains = cells
+        .iter()
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let (lows, highs) = remaining_bounds(&domains);
+    let target = target as i64;
+    let supports = assignment_supports(&domains, false, |values| {
+        let complete = values.len() == domains.len();
+        let total = values.iter().map(|v| *v as i64).sum::<i64>();
+        let product = values.iter().map(|v| *v as i64).product::<i64>();
+        let largest = values.iter().max().map_or(0, |v| *v as i64);
+        match operation {
+            Operation::Add => {
+                let (low, high) = (lows[values.len()] as i64, highs[values.len()] as i64);
+                total + low <= target && total + high >= target
+            }
+            Operation::Multiply if complete => product == target,
+            Operation::Multiply => target == 0 || (product != 0 && target % product == 0),
+            Operation::Subtract if complete => largest - (total - largest) == target,
+            Operation::Divide if complete => largest != 0 && largest * largest == target * product,
+            Operation::Subtract | Operation::Divide => true,
+        }
+    })?;
+    for (pos, support) in cells.iter().zip(supports) {
+        *changed |= state.get_mut(pos).restrict(|c| support.contains(c))?;
+    }
+    Some(())
+}
+
+/// The lowest and highest totals of the domains from each index to the end.
+fn remaining_bounds(domains: &[Vec<i32>]) -> (Vec<i32>, Vec<i32>) {
+    let mut lows = vec![0; domains.len() + 1];
+    let mut highs = vec![0; domains.len() + 1];
+    for (i, domain) in domains.iter().enumerate().rev() {
+        lows[i] = lows[i + 1] + domain.iter().min().unwrap();
+        highs[i] = highs[i + 1] + domain.iter().max().unwrap();
+    }
+    (lows, highs)
+}
+
+/// Returns the values of each domain that take part in a complete assignment accepted by
+/// `viable`, which is also asked whether partial assignments can still be completed.
+fn assignment_supports<F>(domains: &[Vec<i32>], distinct: bool, viable: F) -> Option<Vec<Vec<i32>>>
+where
+    F: Fn(&[i32]) -> bool,
+{
+    let mut supports = vec![Vec::new(); domains.len()];
+    for (i, domain) in domains.iter().enumerate() {
+        for value in domain {
+            if supports[i].contains(value) {
+                continue;
+            }
+            let mut fixed = domains.to_vec();
+            fixed[i] = vec![*value];
+            let mut values = Vec::new();
+            if !find_assignment(&fixed, distinct, &viable, &mut values) {
+                continue;
+            }
+            for (support, value) in supports.iter_mut().zip(values) {
+                if !support.contains(&value) {
+                    support.push(value);
+                }
+            }
+        }
     }
-    if sum_so_far + lowest_sum > *sum {
+    if supports.iter().any(|support| support.is_empty()) {
         return None;
     }
-    if unknown_cells.len() == 1 {
-        let pos = unknown_cells[0];
-        let cell = state.get_mut(pos);
-        *changed |= cell.limit(|c| sum_so_far + c == *sum)?;
+    Some(supports)
+}
+
+fn find_assignment<F>(
+    domains: &[Vec<i32>],
+    distinct: bool,
+    viable: &F,
+    values: &mut Vec<i32>,
+) -> bool
+where
+    F: Fn(&[i32]) -> bool,
+{
+    if !viable(values) {
+        return false;
     }
-    Some(())
+    if values.len() == domains.len() {
+        return true;
+    }
+    for value in &domains[values.len()] {
+        if distinct && values.contains(value) {
+            continue;
+        }
+        values.push(*value);
+        if find_assignment(domains, distinct, viable, values) {
+            return true;
+        }
+        values.pop();
+    }
+    false
 }
 
 fn limit_relationship_clue(
@@ -235,225 +490,869 @@ fn limit_relationship_clue(
     state: &mut SolverState,
     changed: &mut bool,
 ) -> Option<()> {
-    let first_value = state.get(first).value;
-    let second_value = state.get(second).value;
-    if first_value.is_none() && second_value.is_none() {
-        return None;
+    limit_pair(first, second, state, changed, |a, b| {
+        relationship_holds(relationship, a, b)
+    })
+}
+
+fn relationship_holds(relationship: &Relationship, first: i32, second: i32) -> bool {
+    match relationship {
+        Relationship::Less => first < second,
+        Relationship::Greater => first > second,
+        Relationship::Equal => first == second,
+        Relationship::NotEqual => first != second,
+        Relationship::Consecutive => (first - second).abs() == 1,
+        Relationship::Double => first == second * 2 || second == first * 2,
+        Relationship::Sum(sum) => first + second == *sum,
+        Relationship::Difference(difference) => (first - second).abs() == *difference,
+        Relationship::Ratio(ratio) => first == second * ratio || second == first * ratio,
     }
-    if let (Some(first_value), Some(second_value)) = (first_value, second_value) {
-        match relationship {
-            Relationship::Less => {
-                if first_value >= second_value {
-                    return None;
-                }
-            }
-            Relationship::Greater => {
-                if first_value <= second_value {
-                    return None;
-                }
-            }
-            Relationship::Equal => {
-                if first_value != second_value {
-                    return None;
-                }
-            }
-            Relationship::NotEqual => {
-                if first_value == second_value {
-                    return None;
-                }
-            }
-            Relationship::Consecutive => {
-                let max = std::cmp::max(first_value, second_value);
-                let min = std::cmp::min(first_value, second_value);
-                if max - min != 1 {
-                    return None;
-                }
-            }
-            Relationship::Double => {
-                let max = std::cmp::max(first_value, second_value);
-                let min = std::cmp::min(first_value, second_value);
-                if max != min * 2 {
-                    return None;
-                }
-            }
+}
+
+fn limit_quadruple_clue(
+    cells: &[IVec2],
+    digits: &[i32],
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let mut counts: HashMap<i32, usize> = HashMap::new();
+    for digit in digits {
+        *counts.entry(*digit).or_default() += 1;
+    }
+    for (digit, count) in &counts {
+        let holders = cells
+            .iter()
+            .filter(|pos| state.get(pos).possible_values().contains(digit))
+            .copied()
+            .collect::<Vec<_>>();
+        if holders.len() < *count {
+            return None;
         }
-    } else {
-        let (first_is_present, present, not_present) = if first_value.is_some() {
-            (true, first, second)
-        } else {
-            (false, second, first)
-        };
-        let value = state.get(present).value.unwrap();
-        let cell = state.get_mut(not_present);
-        *changed |= cell.limit(|c| match relationship {
-            Relationship::Less => {
-                if first_is_present {
-                    *c < value
-                } else {
-                    *c > value
-                }
-            }
-            Relationship::Greater => {
-                if first_is_present {
-                    *c > value
-                } else {
-                    *c < value
-                }
+        if holders.len() == *count {
+            for pos in &holders {
+                *changed |= state.get_mut(pos).restrict(|c| c == digit)?;
             }
-            Relationship::Equal => *c == value,
-            Relationship::NotEqual => *c != value,
-            Relationship::Consecutive => *c == value + 1 || *c == value - 1,
-            Relationship::Double => *c == value * 2 || value == *c * 2,
-        })?;
+        }
+    }
+    if digits.len() == cells.len() {
+        for pos in cells {
+            *changed |= state.get_mut(pos).restrict(|c| counts.contains_key(c))?;
+        }
     }
     Some(())
 }
 
 fn limit_property_clue(
     region: &Region,
+    model: &SudokuModel,
     state: &mut SolverState,
     property: &Property,
     changed: &mut bool,
 ) -> Option<()> {
     for pos in &region.cells {
         let cell = state.get_mut(pos);
-        if let Some(value) = cell.value {
-            match property {
-                Property::Even => {
-                    if value % 2 != 0 {
-                        return None;
-                    }
-                }
-                Property::Odd => {
-                    if value % 2 == 0 {
-                        return None;
-                    }
-                }
-                Property::Given(given) => {
-                    if *given != value {
-                        return None;
-                    }
-                }
-            }
-        } else {
-            *changed |= cell.limit(|c| match property {
-                Property::Even => c % 2 == 0,
-                Property::Odd => c % 2 != 0,
-                Property::Given(value) => *c == *value,
-            })?;
-        }
+        *changed |= cell.restrict(|c| property_holds(property, *c, model))?;
     }
     Some(())
 }
 
+fn property_holds(property: &Property, value: i32, model: &SudokuModel) -> bool {
+    let half = model.numbers.len() / 2;
+    match property {
+        Property::Even => value % 2 == 0,
+        Property::Odd => value % 2 != 0,
+        Property::Given(given) => value == *given,
+        Property::OneOf(values) => values.contains(&value),
+        Property::NoneOf(values) => !values.contains(&value),
+        Property::Range { min, max } => (*min..=*max).contains(&value),
+        Property::Residue { modulus, remainder } => {
+            value.rem_euclid(*modulus) == remainder.rem_euclid(*modulus)
+        }
+        Property::Low => model.number_indices[&value] < half,
+        Property::High => model.number_indices[&value] >= model.numbers.len() - half,
+    }
+}
+
 fn limit_thermometer_clue(
     line: &Line,
+    strictness: &Strictness,
     model: &SudokuModel,
     state: &mut SolverState,
     changed: &mut bool,
 ) -> Option<()> {
-    let len = line.cells.len();
-    if len > model.numbers.len() {
+    if matches!(strictness, Strictness::Strict) && line.cells.len() > model.numbers.len() {
         return None;
     }
-    let mut offset = 0;
-    let min_indices: Vec<usize> = (0..len)
-        .map(|i| {
-            let cell = state.get(&line.cells[i]);
-            if let Some(value) = cell.value {
-                let value_index = model.number_indices[&value];
-                if value_index < i + offset {
-                    return None;
-                }
-                offset = value_index - i;
-                Some(value_index)
-            } else {
-                let value_index = i + offset;
-                if value_index >= model.numbers.len() {
-                    return None;
-                }
-                Some(value_index)
-            }
-        })
-        .collect::<Option<_>>()?;
-
-    for (i, pos) in line.cells.iter().enumerate() {
+    let follows = |previous: i32, next: i32| match strictness {
+        Strictness::Strict => previous < next,
+        Strictness::Slow => previous <= next,
+        Strictness::MinStep(step) => next - previous >= *step,
+    };
+    let mut lowest = None;
+    for pos in &line.cells {
         let cell = state.get_mut(pos);
-        if cell.value.is_some() {
-            continue;
+        if let Some(previous) = lowest {
+            *changed |= cell.restrict(|c| follows(previous, *c))?;
+        }
+        lowest = cell.possible_values().iter().min().copied();
+    }
+    let mut highest = None;
+    for pos in line.cells.iter().rev() {
+        let cell = state.get_mut(pos);
+        if let Some(next) = highest {
+            *changed |= cell.restrict(|c| follows(*c, next))?;
         }
-        *changed |= cell.limit(|c| min_indices[i] <= model.number_indices[c])?;
+        highest = cell.possible_values().iter().max().copied();
     }
     Some(())
 }
 
-fn limit_unique_clue(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
-    let mut placed = HashSet::new();
-    for pos in &region.cells {
+fn limit_whisper_clue(
+    line: &Line,
+    difference: &i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    for pair in line.cells.windows(2) {
+        limit_pair(&pair[0], &pair[1], state, changed, |a, b| {
+            (a - b).abs() >= *difference
+        })?;
+    }
+    Some(())
+}
+
+fn limit_renban_clue(
+    line: &Line,
+    model: &SudokuModel,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let len = line.cells.len();
+    if len > model.numbers.len() {
+        return None;
+    }
+    let windows = model
+        .numbers
+        .windows(len)
+        .filter(|window| {
+            let cells_fit = line.cells.iter().all(|pos| {
+                let values = state.get(pos).possible_values();
+                values.iter().any(|c| window.contains(c))
+            });
+            let numbers_fit = window.iter().all(|number| {
+                let mut values = line
+                    .cells
+                    .iter()
+                    .map(|pos| state.get(pos).possible_values());
+                values.any(|values| values.contains(number))
+            });
+            cells_fit && numbers_fit
+        })
+        .collect::<Vec<_>>();
+    if windows.is_empty() {
+        return None;
+    }
+    for pos in &line.cells {
         let cell = state.get_mut(pos);
-        if let Some(value) = cell.value {
-            if !placed.insert(value) {
-                return None;
+        *changed |= cell.restrict(|c| windows.iter().any(|window| window.contains(c)))?;
+    }
+    let region = Region {
+        cells: line.cells.iter().cloned().collect(),
+    };
+    limit_unique_clue(&region, state, changed)
+}
+
+fn limit_endpoint_line<E, I>(
+    line: &Line,
+    state: &mut SolverState,
+    changed: &mut bool,
+    endpoints_allowed: E,
+    interior_allowed: I,
+) -> Option<()>
+where
+    E: Fn(i32, i32) -> bool,
+    I: Fn(i32, i32, i32) -> bool,
+{
+    let cells = &line.cells;
+    if cells.len() < 2 {
+        return Some(());
+    }
+    let first = &cells[0];
+    let last = &cells[cells.len() - 1];
+    let interior = &cells[1..cells.len() - 1];
+    let mut endpoints = Vec::new();
+    for &a in state.get(first).possible_values() {
+        for &b in state.get(last).possible_values() {
+            if !endpoints_allowed(a, b) {
+                continue;
+            }
+            let (low, high) = (a.min(b), a.max(b));
+            let interior_fits = interior.iter().all(|pos| {
+                let values = state.get(pos).possible_values();
+                values.iter().any(|c| interior_allowed(low, high, *c))
+            });
+            if interior_fits {
+                endpoints.push((low, high, a, b));
             }
         }
     }
-    for pos in &region.cells {
+    if endpoints.is_empty() {
+        return None;
+    }
+    *changed |= state
+        .get_mut(first)
+        .restrict(|c| endpoints.iter().any(|(_, _, a, _)| a == c))?;
+    *changed |= state
+        .get_mut(last)
+        .restrict(|c| endpoints.iter().any(|(_, _, _, b)| b == c))?;
+    for pos in interior {
         let cell = state.get_mut(pos);
-        if cell.value.is_some() {
-            continue;
-        }
-        *changed |= cell.limit(|c| !placed.contains(c))?;
+        *changed |= cell.restrict(|c| {
+            endpoints
+                .iter()
+                .any(|(low, high, _, _)| interior_allowed(*low, *high, *c))
+        })?;
     }
-    find_obvious_pairs(region, state, changed)?;
-    find_hidden_pairs(region, state, changed)?;
     Some(())
 }
 
-fn find_obvious_pairs(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
-    let mut pairs = HashMap::new();
-    for pos in &region.cells {
-        let cell = state.get(pos);
-        if cell.value.is_some() {
-            continue;
-        }
-        let key = cell
-            .candidates
+fn limit_region_sum_clue(
+    segments: &[Vec<IVec2>],
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    if segments.len() < 2 {
+        return Some(());
+    }
+    let mut min = i32::MIN;
+    let mut max = i32::MAX;
+    for segment in segments {
+        let (low, high) = sum_bounds(segment, state);
+        min = min.max(low);
+        max = max.min(high);
+    }
+    if min > max {
+        return None;
+    }
+    for segment in segments {
+        limit_sum_bounds(segment, min, max, state, changed)?;
+    }
+    Some(())
+}
+
+fn limit_zipper_clue(line: &Line, state: &mut SolverState, changed: &mut bool) -> Option<()> {
+    let len = line.cells.len();
+    let pairs = (0..len / 2)
+        .map(|i| (line.cells[i], line.cells[len - 1 - i]))
+        .collect::<Vec<_>>();
+    let pair_totals = |state: &SolverState, (first, second): &(IVec2, IVec2)| {
+        let second_values = state.get(second).possible_values();
+        state
+            .get(first)
+            .possible_values()
             .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>()
-            .join(",");
-        let entry = pairs
-            .entry(key)
-            .or_insert_with(|| (HashSet::new(), cell.candidates.clone()));
-        entry.0.insert(pos);
+            .flat_map(|a| second_values.iter().map(move |b| a + b))
+            .collect::<HashSet<_>>()
+    };
+    let mut totals = match len % 2 {
+        1 => state
+            .get(&line.cells[len / 2])
+            .possible_values()
+            .iter()
+            .copied()
+            .collect(),
+        _ => match pairs.first() {
+            Some(pair) => pair_totals(state, pair),
+            None => return Some(()),
+        },
+    };
+    for pair in &pairs {
+        let possible = pair_totals(state, pair);
+        totals.retain(|total| possible.contains(total));
     }
-    for (_, (positions, elements)) in &pairs {
-        if elements.len() == positions.len() {
-            for pos in &region.cells {
-                if positions.contains(&pos) {
-                    continue;
-                }
-                let cell = state.get_mut(pos);
-                if cell.value.is_some() {
-                    continue;
-                }
-                *changed |= cell.limit(|c| !elements.contains(c))?;
-            }
-        }
+    if totals.is_empty() {
+        return None;
+    }
+    if len % 2 == 1 {
+        let centre = state.get_mut(&line.cells[len / 2]);
+        *changed |= centre.restrict(|c| totals.contains(c))?;
+    }
+    for (first, second) in &pairs {
+        limit_pair(first, second, state, changed, |a, b| {
+            totals.contains(&(a + b))
+        })?;
     }
     Some(())
 }
 
-fn find_hidden_pairs(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
-    let possible_numbers = region
-        .cells
-        .iter()
-        .flat_map(|cell| state.get(cell).candidates.clone())
-        .collect::<HashSet<i32>>();
-    let free_spots = region
+fn limit_segment_sum_clue(
+    line: &Line,
+    sum: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let len = line.cells.len();
+    let domains = line
         .cells
         .iter()
-        .filter(|cell| state.get(cell).value.is_none())
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let segments = (0..len)
+        .flat_map(|start| (start + 1..=len).map(move |end| (start, end)))
+        .filter_map(|(start, end)| {
+            let supports = sum_supports(&domains[start..end], sum, sum)?;
+            Some((start, end, supports))
+        })
+        .collect::<Vec<_>>();
+    let mut from_start = vec![false; len + 1];
+    from_start[0] = true;
+    let mut to_end = vec![false; len + 1];
+    to_end[len] = true;
+    for i in 0..len {
+        let ends = segments.iter().filter(|(start, _, _)| *start == i);
+        for (_, end, _) in ends {
+            from_start[*end] |= from_start[i];
+        }
+    }
+    for i in (0..len).rev() {
+        let ends = segments.iter().filter(|(start, _, _)| *start == i);
+        for (_, end, _) in ends {
+            to_end[i] |= to_end[*end];
+        }
+    }
+    if !from_start[len] {
+        return None;
+    }
+    let mut allowed = vec![HashSet::new(); len];
+    for (start, end, supports) in &segments {
+        if !from_start[*start] || !to_end[*end] {
+            continue;
+        }
+        for (i, support) in supports.iter().enumerate() {
+            allowed[start + i].extend(support.iter().copied());
+        }
+    }
+    for (pos, allowed) in line.cells.iter().zip(allowed) {
+        *changed |= state.get_mut(pos).restrict(|c| allowed.contains(c))?;
+    }
+    Some(())
+}
+
+fn limit_sandwich_clue(
+    line: &Line,
+    sum: i32,
+    markers: &Option<(i32, i32)>,
+    model: &SudokuModel,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let (low, high) = markers.unwrap_or((model.numbers[0], model.numbers[model.numbers.len() - 1]));
+    let is_marker = |c: &i32| *c == low || *c == high;
+    let domains = line
+        .cells
+        .iter()
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let fillers = domains
+        .iter()
+        .map(|domain| domain.iter().filter(|c| !is_marker(c)).copied().collect())
+        .collect::<Vec<Vec<i32>>>();
+    let mut allowed = vec![HashSet::new(); domains.len()];
+    for start in 0..domains.len() {
+        for end in start + 1..domains.len() {
+            for (first, last) in [(low, high), (high, low)] {
+                if !domains[start].contains(&first) || !domains[end].contains(&last) {
+                    continue;
+                }
+                let Some(supports) = sum_supports(&fillers[start + 1..end], sum, sum) else {
+                    continue;
+                };
+                let outside = (0..domains.len()).filter(|i| *i < start || *i > end);
+                if outside.clone().any(|i| fillers[i].is_empty()) {
+                    continue;
+                }
+                allowed[start].insert(first);
+                allowed[end].insert(last);
+                for (i, support) in supports.into_iter().enumerate() {
+                    allowed[start + 1 + i].extend(support);
+                }
+                for i in outside {
+                    allowed[i].extend(fillers[i].iter().copied());
+                }
+            }
+        }
+    }
+    for (pos, allowed) in line.cells.iter().zip(allowed) {
+        *changed |= state.get_mut(pos).restrict(|c| allowed.contains(c))?;
+    }
+    Some(())
+}
+
+fn limit_skyscraper_clue(
+    line: &Line,
+    count: usize,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let see = |(tallest, visible): (i32, usize), c: i32| {
+        if c > tallest {
+            (c, visible + 1)
+        } else {
+            (tallest, visible)
+        }
+    };
+    let domains = line
+        .cells
+        .iter()
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let mut views = vec![HashSet::from([(i32::MIN, 0)])];
+    for domain in &domains {
+        let next = views
+            .last()
+            .unwrap()
+            .iter()
+            .flat_map(|view| domain.iter().map(|c| see(*view, *c)))
+            .filter(|(_, visible)| *visible <= count)
+            .collect();
+        views.push(next);
+    }
+    let mut reachable = views[domains.len()]
+        .iter()
+        .filter(|(_, visible)| *visible == count)
+        .copied()
+        .collect::<HashSet<_>>();
+    let mut allowed = vec![HashSet::new(); domains.len()];
+    for i in (0..domains.len()).rev() {
+        let mut previous = HashSet::new();
+        for view in &views[i] {
+            for c in &domains[i] {
+                if reachable.contains(&see(*view, *c)) {
+                    allowed[i].insert(*c);
+                    previous.insert(*view);
+                }
+            }
+        }
+        reachable = previous;
+    }
+    for (pos, allowed) in line.cells.iter().zip(allowed) {
+        *changed |= state.get_mut(pos).restrict(|c| allowed.contains(c))?;
+    }
+    Some(())
+}
+
+fn limit_x_sum_clue(
+    line: &Line,
+    sum: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let domains = line
+        .cells
+        .iter()
+        .map(|pos| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let mut allowed = vec![HashSet::new(); domains.len()];
+    for &length in &domains[0] {
+        if length < 1 || length as usize > domains.len() {
+            continue;
+        }
+        let length = length as usize;
+        let mut counted = domains[..length].to_vec();
+        counted[0] = vec![length as i32];
+        let Some(supports) = sum_supports(&counted, sum, sum) else {
+            continue;
+        };
+        for (i, support) in supports.into_iter().enumerate() {
+            allowed[i].extend(support);
+        }
+        for i in length..domains.len() {
+            allowed[i].extend(domains[i].iter().copied());
+        }
+    }
+    for (pos, allowed) in line.cells.iter().zip(allowed) {
+        *changed |= state.get_mut(pos).restrict(|c| allowed.contains(c))?;
+    }
+    Some(())
+}
+
+fn limit_anti_move_clue(
+    offsets: &[IVec2],
+    model: &SudokuModel,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let solved = state
+        .grid
+        .iter()
+        .flatten()
+        .filter_map(|cell| cell.value.map(|value| (cell.pos, value)))
+        .collect::<Vec<_>>();
+    for (pos, value) in solved {
+        for offset in offsets {
+            let target = pos + *offset;
+            if !in_grid(&target, &model.size) || !state.get(&target).active {
+                continue;
+            }
+            *changed |= state.get_mut(&target).restrict(|c| *c != value)?;
+        }
+    }
+    Some(())
+}
+
+fn sum_bounds(cells: &[IVec2], state: &SolverState) -> (i32, i32) {
+    cells.iter().fold((0, 0), |(low, high), pos| {
+        let values = state.get(pos).possible_values();
+        let min = values.iter().min().unwrap();
+        let max = values.iter().max().unwrap();
+        (low + min, high + max)
+    })
+}
+
+fn limit_sum_bounds(
+    cells: &[IVec2],
+    min: i32,
+    max: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let terms = cells.iter().map(|pos| (*pos, 1)).collect::<Vec<_>>();
+    limit_linear_bounds(&terms, min, max, state, changed)
+}
+
+fn limit_cardinality_clue(
+    region: &Region,
+    counts: &[DigitCount],
+    model: &SudokuModel,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let default = counts.iter().find(|count| count.digit.is_none());
+    for number in &model.numbers {
+        let count = counts
+            .iter()
+            .find(|count| count.digit == Some(*number))
+            .or(default);
+        let Some(DigitCount { min, max, .. }) = count else {
+            continue;
+        };
+        let placed = region
+            .cells
+            .iter()
+            .filter(|pos| state.get(pos).value == Some(*number))
+            .count();
+        let open = region
+            .cells
+            .iter()
+            .filter(|pos| state.get(pos).candidates.contains(number))
+            .copied()
+            .collect::<Vec<_>>();
+        if placed > *max || placed + open.len() < *min {
+            return None;
+        }
+        if placed == *max {
+            for pos in &open {
+                *changed |= state.get_mut(pos).limit(|c| c != number)?;
+            }
+        } else if placed + open.len() == *min {
+            for pos in &open {
+                *changed |= state.get_mut(pos).limit(|c| c == number)?;
+            }
+        }
+    }
+    Some(())
+}
+
+fn limit_linear_clue(
+    terms: &[(IVec2, i32)],
+    comparison: &Comparison,
+    constant: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    match comparison {
+        Comparison::Equal => limit_linear_bounds(terms, constant, constant, state, changed),
+        Comparison::Less => {
+            let max = constant.checked_sub(1)?;
+            limit_linear_bounds(terms, i32::MIN, max, state, changed)
+        }
+        Comparison::Greater => {
+            let min = constant.checked_add(1)?;
+            limit_linear_bounds(terms, min, i32::MAX, state, changed)
+        }
+        Comparison::NotEqual => {
+            let open = terms
+                .iter()
+                .filter(|(pos, _)| state.get(pos).possible_values().len() != 1)
+                .collect::<Vec<_>>();
+            if open.len() > 1 {
+                return Some(());
+            }
+            let fixed: i64 = terms
+                .iter()
+                .filter(|term| !open.contains(term))
+                .map(|(pos, weight)| state.get(pos).possible_values()[0] as i64 * *weight as i64)
+                .sum();
+            let constant = constant as i64;
+            match open.first() {
+                Some((pos, weight)) => {
+                    *changed |= state
+                        .get_mut(pos)
+                        .restrict(|c| fixed + *c as i64 * *weight as i64 != constant)?;
+                    Some(())
+                }
+                None => (fixed != constant).then_some(()),
+            }
+        }
+    }
+}
+
+fn limit_linear_bounds(
+    terms: &[(IVec2, i32)],
+    min: i32,
+    max: i32,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let domains = terms
+        .iter()
+        .map(|(pos, _)| state.get(pos).possible_values().to_vec())
+        .collect::<Vec<_>>();
+    let weights = terms.iter().map(|(_, weight)| *weight).collect::<Vec<_>>();
+    let supports = linear_supports(&domains, &weights, min, max)?;
+    for ((pos, _), support) in terms.iter().zip(supports) {
+        *changed |= state.get_mut(pos).restrict(|c| support.contains(c))?;
+    }
+    Some(())
+}
+
+/// Returns the values of each domain that can be part of a total within `min..=max`.
+fn sum_supports(domains: &[Vec<i32>], min: i32, max: i32) -> Option<Vec<Vec<i32>>> {
+    linear_supports(domains, &vec![1; domains.len()], min, max)
+}
+
+/// Returns the values of each domain that can be part of a weighted total within `min..=max`.
+fn linear_supports(
+    domains: &[Vec<i32>],
+    weights: &[i32],
+    min: i32,
+    max: i32,
+) -> Option<Vec<Vec<i32>>> {
+    let bounds = domains
+        .iter()
+        .zip(weights)
+        .map(|(domain, weight)| {
+            let terms = domain.iter().map(|c| *c as i64 * *weight as i64);
+            Some((terms.clone().min()?, terms.max()?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let (min, max) = (min as i64, max as i64);
+    let low: i64 = bounds.iter().map(|(lowest, _)| lowest).sum();
+    let high: i64 = bounds.iter().map(|(_, highest)| highest).sum();
+    if low > max || high < min {
+        return None;
+    }
+    let supports = domains
+        .iter()
+        .zip(weights)
+        .zip(&bounds)
+        .map(|((domain, weight), (lowest, highest))| {
+            domain
+                .iter()
+                .filter(|c| {
+                    let term = **c as i64 * *weight as i64;
+                    low - lowest + term <= max && high - highest + term >= min
+                })
+                .copied()
+                .collect()
+        })
+        .collect::<Vec<Vec<i32>>>();
+    if supports.iter().any(|support| support.is_empty()) {
+        return None;
+    }
+    Some(supports)
+}
+
+/// Limits a line where every `period` consecutive cells fall into distinct classes, which means
+/// cells a multiple of `period` apart always share a class.
+fn limit_class_line<F>(
+    line: &Line,
+    period: usize,
+    class_of: F,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()>
+where
+    F: Fn(i32) -> usize,
+{
+    if line.cells.len() < period {
+        return Some(());
+    }
+    let assignments = (0..period.pow(period as u32))
+        .map(|code| {
+            (0..period)
+                .map(|group| code / period.pow(group as u32) % period)
+                .collect::<Vec<_>>()
+        })
+        .filter(|classes| {
+            let distinct = classes
+                .iter()
+                .enumerate()
+                .all(|(i, class)| !classes[..i].contains(class));
+            let fits = line.cells.iter().enumerate().all(|(i, pos)| {
+                let values = state.get(pos).possible_values();
+                values.iter().any(|c| class_of(*c) == classes[i % period])
+            });
+            distinct && fits
+        })
+        .collect::<Vec<_>>();
+    if assignments.is_empty() {
+        return None;
+    }
+    for (i, pos) in line.cells.iter().enumerate() {
+        let cell = state.get_mut(pos);
+        *changed |= cell.restrict(|c| {
+            let class = class_of(*c);
+            assignments
+                .iter()
+                .any(|classes| classes[i % period] == class)
+        })?;
+    }
+    Some(())
+}
+
+fn limit_indexing_clue(
+    region: &Region,
+    digit: i32,
+    model: &SudokuModel,
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let target = |pos: &IVec2, value: i32| IVec2::new(model.number_indices[&value] as i32, pos.y);
+    for pos in &region.cells {
+        let allowed = state
+            .get(pos)
+            .possible_values()
+            .iter()
+            .copied()
+            .filter(|c| {
+                let cell = target(pos, *c);
+                in_grid(&cell, &model.size) && state.get(&cell).possible_values().contains(&digit)
+            })
+            .collect::<HashSet<_>>();
+        *changed |= state.get_mut(pos).restrict(|c| allowed.contains(c))?;
+        if let Some(value) = state.get(pos).value {
+            let cell = state.get_mut(&target(pos, value));
+            *changed |= cell.restrict(|c| *c == digit)?;
+        }
+    }
+    Some(())
+}
+
+fn limit_arrow_clue(
+    region: &Region,
+    tail: &[IVec2],
+    state: &mut SolverState,
+    changed: &mut bool,
+) -> Option<()> {
+    let shaft = region.cells.iter().map(|pos| (*pos, 1));
+    let bulb = tail
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(place, pos)| (*pos, -(10i32.pow(place as u32))));
+    let terms = shaft.chain(bulb).collect::<Vec<_>>();
+    limit_linear_bounds(&terms, 0, 0, state, changed)
+}
+
+fn limit_pair<F>(
+    first: &IVec2,
+    second: &IVec2,
+    state: &mut SolverState,
+    changed: &mut bool,
+    allowed: F,
+) -> Option<()>
+where
+    F: Fn(i32, i32) -> bool,
+{
+    let second_values = state.get(second).possible_values().to_vec();
+    *changed |= state
+        .get_mut(first)
+        .restrict(|a| second_values.iter().any(|b| allowed(*a, *b)))?;
+    let first_values = state.get(first).possible_values().to_vec();
+    *changed |= state
+        .get_mut(second)
+        .restrict(|b| first_values.iter().any(|a| allowed(*a, *b)))?;
+    Some(())
+}
+
+fn limit_unique_clue(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
+    let mut placed = HashSet::new();
+    for pos in &region.cells {
+        let cell = state.get_mut(pos);
+        if let Some(value) = cell.value {
+            if !placed.insert(value) {
+                return None;
+            }
+        }
+    }
+    for pos in &region.cells {
+        let cell = state.get_mut(pos);
+        if cell.value.is_some() {
+            continue;
+        }
+        *changed |= cell.limit(|c| !placed.contains(c))?;
+    }
+    find_obvious_pairs(region, state, changed)?;
+    find_hidden_pairs(region, state, changed)?;
+    Some(())
+}
+
+fn find_obvious_pairs(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
+    let mut pairs = HashMap::new();
+    for pos in &region.cells {
+        let cell = state.get(pos);
+        if cell.value.is_some() {
+            continue;
+        }
+        let key = cell
+            .candidates
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let entry = pairs
+            .entry(key)
+            .or_insert_with(|| (HashSet::new(), cell.candidates.clone()));
+        entry.0.insert(pos);
+    }
+    for (positions, elements) in pairs.values() {
+        if elements.len() == positions.len() {
+            for pos in &region.cells {
+                if positions.contains(&pos) {
+                    continue;
+                }
+                let cell = state.get_mut(pos);
+                if cell.value.is_some() {
+                    continue;
+                }
+                *changed |= cell.limit(|c| !elements.contains(c))?;
+            }
+        }
+    }
+    Some(())
+}
+
+fn find_hidden_pairs(region: &Region, state: &mut SolverState, changed: &mut bool) -> Option<()> {
+    let possible_numbers = region
+        .cells
+        .iter()
+        .flat_map(|cell| state.get(cell).candidates.clone())
+        .collect::<HashSet<i32>>();
+    let free_spots = region
+        .cells
+        .iter()
+        .filter(|cell| state.get(cell).value.is_none())
         .collect::<Vec<_>>();
     if possible_numbers.len() < free_spots.len() {
         return None;
@@ -486,7 +1385,7 @@ fn find_hidden_pairs(region: &Region, state: &mut SolverState, changed: &mut boo
                 .0
                 .push(*num);
         }
-        for (_, (numbers, spots)) in &possible_spots_inverse {
+        for (numbers, spots) in possible_spots_inverse.values() {
             if numbers.len() == spots.len() {
                 for pos in spots {
                     let cell = state.get_mut(pos);
@@ -497,3 +1396,439 @@ fn find_hidden_pairs(region: &Region, state: &mut SolverState, changed: &mut boo
     }
     Some(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::constraint::{ArrowTail, ConstraintSpecifier};
+    use crate::model::region::RegionSpecifier;
+    use crate::model::SudokuSpecifier;
+
+    fn row_specifier(len: i32, highest: i32) -> SudokuSpecifier {
+        SudokuSpecifier {
+            size: IVec2::new(len, 1),
+            numbers: vec![IVec2::new(1, highest)],
+            constraints: Vec::new(),
+            domains: Vec::new(),
+            inactive: Vec::new(),
+        }
+    }
+
+    fn row(len: i32) -> Line {
+        Line {
+            cells: (0..len).map(|x| IVec2::new(x, 0)).collect(),
+        }
+    }
+
+    /// Places the givens, then runs `limit` until nothing changes and returns the values left in
+    /// each cell of the first row, or `None` on a contradiction.
+    fn propagate<F>(
+        specifier: SudokuSpecifier,
+        givens: &[(i32, i32)],
+        limit: F,
+    ) -> Option<Vec<Vec<i32>>>
+    where
+        F: Fn(&SudokuModel, &mut SolverState, &mut bool) -> Option<()>,
+    {
+        let model = specifier.build_model();
+        let precomputed = Precomputed::new(&model);
+        let mut state = SolverState {
+            grid: empty_grid(&model),
+            precomputed: &precomputed,
+        };
+        for (x, value) in givens {
+            state.get_mut(&IVec2::new(*x, 0)).set_value(*value);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            limit(&model, &mut state, &mut changed)?;
+        }
+        let values = (0..model.size.x)
+            .map(|x| state.get(&IVec2::new(x, 0)).possible_values().to_vec())
+            .collect();
+        Some(values)
+    }
+
+    fn limit_all(model: &SudokuModel, state: &mut SolverState, changed: &mut bool) -> Option<()> {
+        for constraint in &model.constraints {
+            *changed |= limit_state(model, state, constraint)?;
+        }
+        Some(())
+    }
+
+    #[test]
+    fn sandwich_with_zero_sum_needs_adjacent_markers() {
+        let values = propagate(row_specifier(4, 4), &[(0, 1)], |model, state, changed| {
+            limit_sandwich_clue(&row(4), 0, &None, model, state, changed)
+        });
+        assert_eq!(values.unwrap()[1], vec![4]);
+    }
+
+    #[test]
+    fn sandwich_with_unreachable_sum_fails() {
+        let values = propagate(row_specifier(4, 4), &[], |model, state, changed| {
+            limit_sandwich_clue(&row(4), 10, &None, model, state, changed)
+        });
+        assert!(values.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Sandwich markers must be different numbers")]
+    fn sandwich_with_equal_markers_is_rejected() {
+        let sandwich = ConstraintSpecifier::Sandwich {
+            position: IVec2::new(-1, 0),
+            sum: 0,
+            markers: Some((2, 2)),
+        };
+        sandwich.build_constraint(&IVec2::new(4, 1));
+    }
+
+    #[test]
+    fn skyscraper_seeing_every_cell_is_increasing() {
+        let values = propagate(row_specifier(4, 4), &[], |_, state, changed| {
+            limit_skyscraper_clue(&row(4), 4, state, changed)
+        });
+        assert_eq!(values.unwrap(), vec![vec![1], vec![2], vec![3], vec![4]]);
+    }
+
+    #[test]
+    fn skyscraper_seeing_one_cell_hides_the_rest() {
+        let values = propagate(row_specifier(4, 4), &[(3, 4)], |_, state, changed| {
+            limit_skyscraper_clue(&row(4), 1, state, changed)
+        });
+        assert_eq!(values.unwrap()[0], vec![4]);
+    }
+
+    #[test]
+    fn skyscraper_count_beyond_line_length_fails() {
+        let values = propagate(row_specifier(4, 4), &[], |_, state, changed| {
+            limit_skyscraper_clue(&row(4), 5, state, changed)
+        });
+        assert!(values.is_none());
+    }
+
+    #[test]
+    fn x_sum_rules_out_counts_longer_than_the_line() {
+        let values = propagate(row_specifier(3, 4), &[], |_, state, changed| {
+            limit_x_sum_clue(&row(3), 6, state, changed)
+        });
+        assert_eq!(values.unwrap()[0], vec![2, 3]);
+    }
+
+    #[test]
+    fn x_sum_of_one_fixes_the_first_cell() {
+        let values = propagate(row_specifier(3, 4), &[], |_, state, changed| {
+            limit_x_sum_clue(&row(3), 1, state, changed)
+        });
+        assert_eq!(values.unwrap()[0], vec![1]);
+    }
+
+    fn with_constraints(
+        mut specifier: SudokuSpecifier,
+        constraints: Vec<ConstraintSpecifier>,
+    ) -> SudokuSpecifier {
+        specifier.constraints = constraints;
+        specifier
+    }
+
+    fn relationship(first: i32, second: i32, relationship: Relationship) -> ConstraintSpecifier {
+        ConstraintSpecifier::Relationship {
+            first: IVec2::new(first, 0),
+            second: IVec2::new(second, 0),
+            relationship,
+        }
+    }
+
+    #[test]
+    fn relationship_limits_both_cells() {
+        let specifier = with_constraints(
+            row_specifier(2, 4),
+            vec![relationship(0, 1, Relationship::Greater)],
+        );
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![2, 3, 4], vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn broken_relationship_fails() {
+        let specifier = with_constraints(
+            row_specifier(2, 4),
+            vec![relationship(0, 1, Relationship::Greater)],
+        );
+        assert!(propagate(specifier, &[(0, 1)], limit_all).is_none());
+    }
+
+    #[test]
+    fn negative_limits_unmarked_pairs() {
+        let specifier = with_constraints(
+            row_specifier(2, 4),
+            vec![ConstraintSpecifier::Negative {
+                relationships: vec![Relationship::Consecutive],
+            }],
+        );
+        let values = propagate(specifier, &[(0, 2)], limit_all);
+        assert_eq!(values.unwrap()[1], vec![2, 4]);
+    }
+
+    #[test]
+    fn negative_applies_to_pairs_with_other_markers() {
+        let specifier = with_constraints(
+            row_specifier(2, 4),
+            vec![
+                relationship(0, 1, Relationship::Less),
+                ConstraintSpecifier::Negative {
+                    relationships: vec![Relationship::Consecutive],
+                },
+            ],
+        );
+        assert!(propagate(specifier, &[(0, 1), (1, 2)], limit_all).is_none());
+    }
+
+    #[test]
+    fn default_negative_applies_to_pairs_with_other_marker_kinds() {
+        let specifier = with_constraints(
+            row_specifier(3, 4),
+            vec![
+                relationship(0, 1, Relationship::Sum(5)),
+                relationship(1, 2, Relationship::Consecutive),
+                ConstraintSpecifier::Negative {
+                    relationships: Vec::new(),
+                },
+            ],
+        );
+        assert!(propagate(specifier, &[(0, 2), (1, 3)], limit_all).is_none());
+    }
+
+    fn cage(len: i32, sum: i32, allow_repeats: bool) -> ConstraintSpecifier {
+        ConstraintSpecifier::Killer {
+            region: RegionSpecifier::Many {
+                cells: (0..len).map(|x| IVec2::new(x, 0)).collect(),
+            },
+            sum: Some(sum),
+            allow_repeats,
+        }
+    }
+
+    #[test]
+    fn killer_limits_cells_to_distinct_sums() {
+        let specifier = with_constraints(row_specifier(2, 4), vec![cage(2, 3, false)]);
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![1, 2], vec![1, 2]]);
+    }
+
+    #[test]
+    fn killer_sum_needing_repeats_fails() {
+        let specifier = with_constraints(row_specifier(2, 4), vec![cage(2, 8, false)]);
+        assert!(propagate(specifier, &[], limit_all).is_none());
+    }
+
+    #[test]
+    fn killer_with_repeats_allows_equal_digits() {
+        let specifier = with_constraints(row_specifier(2, 4), vec![cage(2, 8, true)]);
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![4], vec![4]]);
+    }
+
+    fn arithmetic(len: i32, operation: Operation, target: i32) -> ConstraintSpecifier {
+        ConstraintSpecifier::Arithmetic {
+            region: RegionSpecifier::Many {
+                cells: (0..len).map(|x| IVec2::new(x, 0)).collect(),
+            },
+            operation,
+            target,
+        }
+    }
+
+    #[test]
+    fn arithmetic_product_limits_cells_to_factors() {
+        let constraint = arithmetic(2, Operation::Multiply, 6);
+        let specifier = with_constraints(row_specifier(2, 4), vec![constraint]);
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![2, 3], vec![2, 3]]);
+    }
+
+    #[test]
+    fn arithmetic_quotient_limits_cells() {
+        let constraint = arithmetic(2, Operation::Divide, 3);
+        let specifier = with_constraints(row_specifier(2, 4), vec![constraint]);
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![1, 3], vec![1, 3]]);
+    }
+
+    #[test]
+    fn arithmetic_unreachable_difference_fails() {
+        let constraint = arithmetic(2, Operation::Subtract, 4);
+        let specifier = with_constraints(row_specifier(2, 4), vec![constraint]);
+        assert!(propagate(specifier, &[], limit_all).is_none());
+    }
+
+    /// An arrow whose two-cell pill starts the row and whose shaft fills the rest of it.
+    fn pill_arrow(len: i32) -> ConstraintSpecifier {
+        ConstraintSpecifier::Arrow {
+            region: RegionSpecifier::Many {
+                cells: (2..len).map(|x| IVec2::new(x, 0)).collect(),
+            },
+            tail: ArrowTail::Pill(vec![IVec2::new(0, 0), IVec2::new(1, 0)]),
+        }
+    }
+
+    #[test]
+    fn pill_arrow_limits_tens_digit() {
+        let specifier = with_constraints(row_specifier(4, 9), vec![pill_arrow(4)]);
+        let values = propagate(specifier, &[], limit_all).unwrap();
+        assert_eq!(values[0], vec![1]);
+        assert_eq!(values[1], (1..=8).collect::<Vec<_>>());
+        assert_eq!(values[2], (2..=9).collect::<Vec<_>>());
+        assert_eq!(values[3], (2..=9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn pill_arrow_with_short_shaft_fails() {
+        let specifier = with_constraints(row_specifier(3, 9), vec![pill_arrow(3)]);
+        assert!(propagate(specifier, &[], limit_all).is_none());
+    }
+
+    #[test]
+    fn entropic_line_shorter_than_period_is_unrestricted() {
+        let values = propagate(
+            row_specifier(2, 9),
+            &[(0, 1), (1, 2)],
+            |model, state, changed| {
+                *changed |= limit_state(model, state, &Constraint::Entropic(row(2)))?;
+                Some(())
+            },
+        );
+        assert_eq!(values.unwrap(), vec![vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn entropic_line_limits_third_cell() {
+        let values = propagate(
+            row_specifier(3, 9),
+            &[(0, 1), (1, 5)],
+            |model, state, changed| {
+                *changed |= limit_state(model, state, &Constraint::Entropic(row(3)))?;
+                Some(())
+            },
+        );
+        assert_eq!(values.unwrap()[2], vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn segment_sum_completes_first_segment() {
+        let values = propagate(row_specifier(4, 9), &[(0, 9)], |_, state, changed| {
+            limit_segment_sum_clue(&row(4), 10, state, changed)
+        });
+        assert_eq!(values.unwrap()[1], vec![1]);
+    }
+
+    #[test]
+    fn segment_sum_out_of_reach_fails() {
+        let values = propagate(row_specifier(2, 9), &[], |_, state, changed| {
+            limit_segment_sum_clue(&row(2), 20, state, changed)
+        });
+        assert!(values.is_none());
+    }
+
+    fn indexing(x: i32, digit: i32) -> ConstraintSpecifier {
+        ConstraintSpecifier::Indexing {
+            region: RegionSpecifier::Many {
+                cells: vec![IVec2::new(x, 0)],
+            },
+            digit,
+        }
+    }
+
+    #[test]
+    fn indexing_places_digit_in_named_column() {
+        let specifier = with_constraints(row_specifier(4, 4), vec![indexing(0, 4)]);
+        let values = propagate(specifier, &[(0, 3)], limit_all);
+        assert_eq!(values.unwrap()[2], vec![4]);
+    }
+
+    #[test]
+    fn indexing_column_without_digit_fails() {
+        let specifier = with_constraints(row_specifier(4, 4), vec![indexing(0, 4)]);
+        assert!(propagate(specifier, &[(0, 2), (1, 3)], limit_all).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Disjoint groups need boxes")]
+    fn disjoint_groups_without_boxes_is_rejected() {
+        let groups = ConstraintSpecifier::DisjointGroups { boxes: vec![] };
+        with_constraints(row_specifier(3, 4), vec![groups]).build_model();
+    }
+
+    fn linear(comparison: Comparison, constant: i32) -> ConstraintSpecifier {
+        ConstraintSpecifier::Linear {
+            terms: vec![(IVec2::new(0, 0), 1), (IVec2::new(1, 0), -1)],
+            comparison,
+            constant,
+        }
+    }
+
+    #[test]
+    fn linear_less_than_limits_cells() {
+        let specifier = with_constraints(row_specifier(2, 4), vec![linear(Comparison::Less, -2)]);
+        let values = propagate(specifier, &[], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![1], vec![4]]);
+    }
+
+    #[test]
+    fn linear_bound_beyond_integer_range_fails() {
+        let less = with_constraints(
+            row_specifier(2, 4),
+            vec![linear(Comparison::Less, i32::MIN)],
+        );
+        assert!(propagate(less, &[], limit_all).is_none());
+        let greater = linear(Comparison::Greater, i32::MAX);
+        let greater = with_constraints(row_specifier(2, 4), vec![greater]);
+        assert!(propagate(greater, &[], limit_all).is_none());
+    }
+
+    #[test]
+    fn linear_with_large_weights_does_not_overflow() {
+        let large = ConstraintSpecifier::Linear {
+            terms: vec![(IVec2::new(0, 0), i32::MAX), (IVec2::new(1, 0), -i32::MAX)],
+            comparison: Comparison::Equal,
+            constant: 0,
+        };
+        let specifier = with_constraints(row_specifier(2, 4), vec![large]);
+        let values = propagate(specifier, &[(0, 3)], limit_all);
+        assert_eq!(values.unwrap()[1], vec![3]);
+    }
+
+    fn cardinality(len: i32, digit: i32, min: usize, max: usize) -> ConstraintSpecifier {
+        ConstraintSpecifier::Cardinality {
+            region: RegionSpecifier::Many {
+                cells: (0..len).map(|x| IVec2::new(x, 0)).collect(),
+            },
+            counts: vec![DigitCount {
+                digit: Some(digit),
+                min,
+                max,
+            }],
+        }
+    }
+
+    #[test]
+    fn cardinality_fills_remaining_cells_with_required_digit() {
+        let specifier = with_constraints(row_specifier(3, 4), vec![cardinality(3, 4, 2, 2)]);
+        let values = propagate(specifier, &[(0, 1)], limit_all);
+        assert_eq!(values.unwrap(), vec![vec![1], vec![4], vec![4]]);
+    }
+
+    #[test]
+    fn cardinality_removes_digit_at_its_maximum() {
+        let specifier = with_constraints(row_specifier(3, 4), vec![cardinality(3, 1, 0, 1)]);
+        let values = propagate(specifier, &[(0, 1)], limit_all);
+        assert_eq!(values.unwrap()[1], vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn cardinality_above_maximum_fails() {
+        let specifier = with_constraints(row_specifier(3, 4), vec![cardinality(3, 1, 0, 1)]);
+        assert!(propagate(specifier, &[(0, 1), (1, 1)], limit_all).is_none());
+    }
+}
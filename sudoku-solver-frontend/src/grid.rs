@@ -182,4 +182,17 @@ fn handle_keyboard_input_debug(
             Relationship::Double,
         ));
     }
+    if keyboard_button_input.just_pressed(KeyCode::Numpad5) {
+        ev_spawn_constraint.send(SpawnConstraintEvent::Relationship(
+            Relationship::Sum(10),
+        ));
+    }
+    if keyboard_button_input.just_pressed(KeyCode::Numpad6) {
+        ev_spawn_constraint.send(SpawnConstraintEvent::Relationship(
+            Relationship::Sum(5),
+        ));
+    }
+    if keyboard_button_input.just_pressed(KeyCode::Numpad7) {
+        ev_spawn_constraint.send(SpawnConstraintEvent::Quadruple(vec![1, 2, 3, 4]));
+    }
 }
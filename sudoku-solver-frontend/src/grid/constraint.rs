@@ -21,6 +21,7 @@ pub enum SpawnConstraintEvent {
     Thermometer,
     Unique,
     Relationship(Relationship),
+    Quadruple(Vec<i32>),
 }
 
 #[derive(Component)]
@@ -35,6 +36,9 @@ pub struct ConstraintUnique;
 #[derive(Component)]
 pub struct ConstraintRelationship(Relationship);
 
+#[derive(Component)]
+pub struct ConstraintQuadruple(Vec<i32>);
+
 #[derive(Component)]
 pub struct CellRegion {
     pub cells: HashSet<IVec2>,
@@ -57,6 +61,7 @@ pub struct Constraints {
     pub thermometers: HashMap<IVec2, Entity>,
     pub uniques: HashMap<IVec2, Entity>,
     pub relationships: HashMap<UnorderedPair, Entity>,
+    pub quadruples: HashMap<IVec2, Entity>,
 }
 
 pub fn constraints_plugin(app: &mut App) {
@@ -69,6 +74,7 @@ pub fn constraints_plugin(app: &mut App) {
                 handle_spawn_thermometer,
                 handle_spawn_unique,
                 handle_spawn_relationships,
+                handle_spawn_quadruple,
             ),
         );
 }
@@ -79,6 +85,7 @@ fn setup_constraints(mut commands: Commands) {
         thermometers: HashMap::default(),
         uniques: HashMap::default(),
         relationships: HashMap::default(),
+        quadruples: HashMap::default(),
     },));
 }
 
@@ -293,6 +300,7 @@ fn handle_spawn_unique(
 fn handle_spawn_relationships(
     mut commands: Commands,
     mut constraints: Query<&mut Constraints>,
+    fonts: Res<Fonts>,
     q_grid: Query<(Entity, &Grid)>,
     q_selection: Query<(&Selector, &SelectionType, &CellEdges)>,
     mut ev_spawn_constraint: EventReader<SpawnConstraintEvent>,
@@ -312,39 +320,55 @@ fn handle_spawn_relationships(
             }
             let center_pos = (edge.0.as_vec2() + edge.1.as_vec2()) / 2.;
             let relationship_entity = match relationship {
-                Relationship::Consecutive => commands
-                    .spawn((
-                        ShapeBundle {
-                            path: GeometryBuilder::build_as(&shapes::Circle {
-                                center: center_pos,
-                                radius: 0.15,
-                            }),
-                            transform: Transform::from_translation(Vec3::ZERO.with_z(0.15)),
-                            ..Default::default()
-                        },
-                        make_stroke(Color::BLACK, 0.02, false),
-                        make_fill(Color::WHITE),
-                        ConstraintRelationship(relationship.clone()),
-                    ))
-                    .id(),
-                Relationship::Double => commands
-                    .spawn((
-                        ShapeBundle {
-                            path: GeometryBuilder::build_as(&shapes::Circle {
-                                center: center_pos,
-                                radius: 0.15,
-                            }),
-                            transform: Transform::from_translation(Vec3::ZERO.with_z(0.15)),
-                            ..Default::default()
-                        },
-                        make_fill(Color::BLACK),
-                        ConstraintRelationship(relationship.clone()),
-                    ))
-                    .id(),
+                Relationship::Consecutive => {
+                    spawn_relationship_dot(&mut commands, center_pos, false)
+                }
+                Relationship::Double => spawn_relationship_dot(&mut commands, center_pos, true),
+                Relationship::Difference(difference) => {
+                    let dot = spawn_relationship_dot(&mut commands, center_pos, false);
+                    let label = spawn_relationship_label(
+                        &mut commands,
+                        &fonts,
+                        center_pos.extend(0.01),
+                        difference.to_string(),
+                        Color::BLACK,
+                    );
+                    commands.entity(dot).add_child(label);
+                    dot
+                }
+                Relationship::Ratio(ratio) => {
+                    let dot = spawn_relationship_dot(&mut commands, center_pos, true);
+                    let label = spawn_relationship_label(
+                        &mut commands,
+                        &fonts,
+                        center_pos.extend(0.01),
+                        ratio.to_string(),
+                        Color::WHITE,
+                    );
+                    commands.entity(dot).add_child(label);
+                    dot
+                }
+                Relationship::Sum(sum) => {
+                    let text = match sum {
+                        5 => "V".to_string(),
+                        10 => "X".to_string(),
+                        _ => sum.to_string(),
+                    };
+                    spawn_relationship_label(
+                        &mut commands,
+                        &fonts,
+                        center_pos.extend(0.15),
+                        text,
+                        Color::BLACK,
+                    )
+                }
                 _ => {
                     continue;
                 }
             };
+            commands
+                .entity(relationship_entity)
+                .insert(ConstraintRelationship(relationship.clone()));
 
             let (grid_entity, _) = q_grid.single();
             commands.entity(grid_entity).add_child(relationship_entity);
@@ -353,3 +377,129 @@ fn handle_spawn_relationships(
         }
     }
 }
+
+fn spawn_relationship_dot(commands: &mut Commands, center_pos: Vec2, filled: bool) -> Entity {
+    let path = GeometryBuilder::build_as(&shapes::Circle {
+        center: center_pos,
+        radius: 0.15,
+    });
+    let mut dot = commands.spawn(ShapeBundle {
+        path,
+        transform: Transform::from_translation(Vec3::ZERO.with_z(0.15)),
+        ..Default::default()
+    });
+    if filled {
+        dot.insert(make_fill(Color::BLACK));
+    } else {
+        dot.insert((
+            make_stroke(Color::BLACK, 0.02, false),
+            make_fill(Color::WHITE),
+        ));
+    }
+    dot.id()
+}
+
+fn spawn_relationship_label(
+    commands: &mut Commands,
+    fonts: &Fonts,
+    pos: Vec3,
+    text: String,
+    color: Color,
+) -> Entity {
+    commands
+        .spawn((
+            Text2d(text),
+            Transform::from_translation(pos).with_scale(Vec3::splat(0.004)),
+            TextFont {
+                font: fonts.fira_mono.clone(),
+                font_size: 60.0,
+                ..Default::default()
+            },
+            TextColor(color),
+        ))
+        .id()
+}
+
+fn handle_spawn_quadruple(
+    mut commands: Commands,
+    mut constraints: Query<&mut Constraints>,
+    fonts: Res<Fonts>,
+    q_grid: Query<(Entity, &Grid)>,
+    q_selection: Query<(&Selector, &SelectionType, &CellRegion)>,
+    mut ev_spawn_constraint: EventReader<SpawnConstraintEvent>,
+) {
+    let Ok((_, SelectionType::Region, selection)) = q_selection.get_single() else {
+        ev_spawn_constraint.read().for_each(drop);
+        return;
+    };
+    let mut constraints = constraints.single_mut();
+    for event in ev_spawn_constraint.read() {
+        let SpawnConstraintEvent::Quadruple(digits) = event else {
+            continue;
+        };
+        let Some(corner) = square_corner(&selection.cells) else {
+            continue;
+        };
+        if constraints.quadruples.contains_key(&corner) {
+            continue;
+        }
+        let center_pos = corner.as_vec2() + Vec2::splat(0.5);
+
+        let quadruple = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Circle {
+                        center: center_pos,
+                        radius: 0.22,
+                    }),
+                    transform: Transform::from_translation(Vec3::ZERO.with_z(0.15)),
+                    ..Default::default()
+                },
+                make_stroke(Color::BLACK, 0.02, false),
+                make_fill(Color::WHITE),
+                ConstraintQuadruple(digits.clone()),
+            ))
+            .id();
+
+        let text = digits
+            .chunks(2)
+            .map(|row| {
+                row.iter()
+                    .map(|digit| digit.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let quadruple_text = commands
+            .spawn((
+                Text2d(text),
+                Transform::from_translation(center_pos.extend(0.01))
+                    .with_scale(Vec3::splat(0.0025)),
+                TextFont {
+                    font: fonts.fira_mono.clone(),
+                    font_size: 60.0,
+                    ..Default::default()
+                },
+                TextColor(Color::BLACK),
+            ))
+            .id();
+
+        let (grid_entity, _) = q_grid.single();
+        commands.entity(grid_entity).add_child(quadruple);
+        commands.entity(quadruple).add_child(quadruple_text);
+
+        constraints.quadruples.insert(corner, quadruple);
+    }
+}
+
+fn square_corner(cells: &HashSet<IVec2>) -> Option<IVec2> {
+    let corner = cells
+        .iter()
+        .fold(IVec2::MAX, |corner, cell| corner.min(*cell));
+    let is_square = cells.len() == 4
+        && [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE]
+            .iter()
+            .all(|offset| cells.contains(&(corner + *offset)));
+    is_square.then_some(corner)
+}